@@ -76,6 +76,23 @@ pub fn max_money(_: Network) -> u64 {
 
 /// Constructs and returns the coinbase (and only) transaction of the Bitcoin genesis block
 fn bitcoin_genesis_tx() -> Transaction {
+    genesis_tx(
+        b"The Times 03/Jan/2009 Chancellor on brink of second bailout for banks",
+        "04678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5f",
+    )
+}
+
+/// Constructs and returns the coinbase (and only) transaction of the testnet4 genesis block
+fn testnet4_genesis_tx() -> Transaction {
+    genesis_tx(
+        b"03/May/2024 000000000000000000001ebd58c244970b3aa9d783bb001011fbe8ea8e98e00e",
+        "000000000000000000000000000000000000000000000000000000000000000000",
+    )
+}
+
+/// Constructs a genesis coinbase transaction with the given message in its input and paying
+/// to the given public key
+fn genesis_tx(message: &[u8], output_pubkey_hex: &str) -> Transaction {
     // Base
     let mut ret = Transaction {
         version: 1,
@@ -87,7 +104,7 @@ fn bitcoin_genesis_tx() -> Transaction {
     // Inputs
     let in_script = script::Builder::new().push_scriptint(486604799)
                                           .push_scriptint(4)
-                                          .push_slice(b"The Times 03/Jan/2009 Chancellor on brink of second bailout for banks")
+                                          .push_slice(message)
                                           .into_script();
     ret.input.push(TxIn {
         previous_output: OutPoint::null(),
@@ -98,7 +115,7 @@ fn bitcoin_genesis_tx() -> Transaction {
 
     // Outputs
     let script_bytes: Result<Vec<u8>, HexError> =
-        HexIterator::new("04678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5f").unwrap()
+        HexIterator::new(output_pubkey_hex).unwrap()
             .collect();
     let out_script = script::Builder::new()
         .push_slice(script_bytes.unwrap().as_slice())
@@ -115,7 +132,10 @@ fn bitcoin_genesis_tx() -> Transaction {
 
 /// Constructs and returns the genesis block
 pub fn genesis_block(network: Network) -> Block {
-    let txdata = vec![bitcoin_genesis_tx()];
+    let txdata = match network {
+        Network::Testnet4 => vec![testnet4_genesis_tx()],
+        _ => vec![bitcoin_genesis_tx()],
+    };
     let hash: sha256d::Hash = txdata[0].txid().into();
     let merkle_root = hash.into();
     match network {
@@ -145,6 +165,19 @@ pub fn genesis_block(network: Network) -> Block {
                 txdata: txdata
             }
         }
+        Network::Testnet4 => {
+            Block {
+                header: BlockHeader {
+                    version: 1,
+                    prev_blockhash: Default::default(),
+                    merkle_root,
+                    time: 1714777860,
+                    bits: 0x1d00ffff,
+                    nonce: 393743547
+                },
+                txdata
+            }
+        }
         Network::Signet => {
             Block {
                 header: BlockHeader {
@@ -235,6 +268,20 @@ mod test {
                    "000000000933ea01ad0ee984209779baaec3ced90fa3f408719526f8d77f4943".to_string());
     }
 
+    #[test]
+    fn testnet4_genesis_full_block() {
+        let gen = genesis_block(Network::Testnet4);
+        assert_eq!(gen.header.version, 1);
+        assert_eq!(gen.header.prev_blockhash, Default::default());
+        assert_eq!(format!("{:x}", gen.header.merkle_root),
+                  "7aa0a7ae1e223414cb807e40cd57e667b718e42aaf9306db9102fe28912b7b4e".to_string());
+        assert_eq!(gen.header.time, 1714777860);
+        assert_eq!(gen.header.bits, 0x1d00ffff);
+        assert_eq!(gen.header.nonce, 393743547);
+        assert_eq!(format!("{:x}", gen.header.block_hash()),
+                   "00000000da84f2bafbbc53dee25a72ae507ff4914b867c565be350b0da8bf043".to_string());
+    }
+
     #[test]
     fn signet_genesis_full_block() {
         let gen = genesis_block(Network::Signet);
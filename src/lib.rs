@@ -35,6 +35,7 @@
 //! * `use-serde` - (dependency) implements `serde`-based serialization and
 //!                 deserialization
 //! * `secp-lowmemory` - optimizations for low-memory devices
+//! * `test-helpers` - exposes dummy constructors for writing tests downstream.
 //! * `no-std` - enables additional features required for this crate to be usable
 //!              without std. Does **not** disable `std`. Depends on `hashbrown`
 //!              and `core2`.
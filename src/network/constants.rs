@@ -67,6 +67,8 @@ user_enum! {
         Bitcoin <-> "bitcoin",
         /// Bitcoin's testnet
         Testnet <-> "testnet",
+        /// Bitcoin's testnet4, which replaces testnet
+        Testnet4 <-> "testnet4",
         /// Bitcoin's signet
         Signet <-> "signet",
         /// Bitcoin's regtest
@@ -90,6 +92,7 @@ impl Network {
         match magic {
             0xD9B4BEF9 => Some(Network::Bitcoin),
             0x0709110B => Some(Network::Testnet),
+            0x283F161C => Some(Network::Testnet4),
             0x40CF030A => Some(Network::Signet),
             0xDAB5BFFA => Some(Network::Regtest),
             _ => None
@@ -112,6 +115,7 @@ impl Network {
         match self {
             Network::Bitcoin => 0xD9B4BEF9,
             Network::Testnet => 0x0709110B,
+            Network::Testnet4 => 0x283F161C,
             Network::Signet  => 0x40CF030A,
             Network::Regtest => 0xDAB5BFFA,
         }
@@ -304,6 +308,10 @@ mod tests {
             serialize(&Network::Testnet.magic()),
             &[0x0b, 0x11, 0x09, 0x07]
         );
+        assert_eq!(
+            serialize(&Network::Testnet4.magic()),
+            &[0x1c, 0x16, 0x3f, 0x28]
+        );
         assert_eq!(
             serialize(&Network::Signet.magic()),
             &[0x0a, 0x03, 0xcf, 0x40]
@@ -321,6 +329,10 @@ mod tests {
             deserialize(&[0x0b, 0x11, 0x09, 0x07]).ok(),
             Some(Network::Testnet.magic())
         );
+        assert_eq!(
+            deserialize(&[0x1c, 0x16, 0x3f, 0x28]).ok(),
+            Some(Network::Testnet4.magic())
+        );
         assert_eq!(
             deserialize(&[0x0a, 0x03, 0xcf, 0x40]).ok(),
             Some(Network::Signet.magic())
@@ -335,11 +347,13 @@ mod tests {
     fn string_test() {
         assert_eq!(Network::Bitcoin.to_string(), "bitcoin");
         assert_eq!(Network::Testnet.to_string(), "testnet");
+        assert_eq!(Network::Testnet4.to_string(), "testnet4");
         assert_eq!(Network::Regtest.to_string(), "regtest");
         assert_eq!(Network::Signet.to_string(), "signet");
 
         assert_eq!("bitcoin".parse::<Network>().unwrap(), Network::Bitcoin);
         assert_eq!("testnet".parse::<Network>().unwrap(), Network::Testnet);
+        assert_eq!("testnet4".parse::<Network>().unwrap(), Network::Testnet4);
         assert_eq!("regtest".parse::<Network>().unwrap(), Network::Regtest);
         assert_eq!("signet".parse::<Network>().unwrap(), Network::Signet);
         assert!("fakenet".parse::<Network>().is_err());
@@ -40,15 +40,19 @@ use core::num::ParseIntError;
 use core::str::FromStr;
 #[cfg(feature = "std")] use std::error;
 
-use secp256k1::schnorrsig;
+use secp256k1::{schnorrsig, Secp256k1, Verification};
 use bech32;
-use hashes::Hash;
+use hashes::{Hash, HashEngine};
+use hashes::hex::ToHex;
 use hash_types::{PubkeyHash, WPubkeyHash, ScriptHash, WScriptHash};
 use blockdata::{script, opcodes};
-use blockdata::constants::{PUBKEY_ADDRESS_PREFIX_MAIN, SCRIPT_ADDRESS_PREFIX_MAIN, PUBKEY_ADDRESS_PREFIX_TEST, SCRIPT_ADDRESS_PREFIX_TEST, MAX_SCRIPT_ELEMENT_SIZE};
+use blockdata::constants::{PUBKEY_ADDRESS_PREFIX_MAIN, SCRIPT_ADDRESS_PREFIX_MAIN, PUBKEY_ADDRESS_PREFIX_TEST, SCRIPT_ADDRESS_PREFIX_TEST, MAX_SCRIPT_ELEMENT_SIZE, WITNESS_SCALE_FACTOR};
+use consensus::encode::{Encodable, VarInt};
 use network::constants::Network;
+use util::amount::Amount;
 use util::base58;
 use util::ecdsa;
+use util::taproot::{TapBranchHash, TapLeafHash, TapTweakHash};
 use blockdata::script::Instruction;
 
 /// Address error.
@@ -73,14 +77,48 @@ pub enum Error {
     UnparsableWitnessVersion(ParseIntError),
     /// Bitcoin script opcode does not match any known witness version, the script is malformed
     MalformedWitnessVersion,
-    /// The witness program must be between 2 and 40 bytes in length.
-    InvalidWitnessProgramLength(usize),
+    /// The witness program is shorter than the minimum of 2 bytes.
+    WitnessProgramTooShort(usize),
+    /// The witness program is longer than the maximum of 40 bytes.
+    WitnessProgramTooLong(usize),
     /// A v0 witness program must be either of length 20 or 32.
     InvalidSegwitV0ProgramLength(usize),
+    /// A v1 witness program must be either of length 32 or 2 (pay-to-anchor).
+    InvalidSegwitV1ProgramLength(usize),
     /// An uncompressed pubkey was used where it is not allowed.
     UncompressedPubkey,
     /// Address size more than 520 bytes is not allowed.
-    ExcessiveScriptSize
+    ExcessiveScriptSize,
+    /// The address payload does not have the length required by its hash type.
+    InvalidHashLength(usize),
+    /// The script does not match the output script template required by the operation.
+    UnrecognizedScript,
+    /// An `addr(...)` descriptor fragment is missing its closing parenthesis or has trailing
+    /// characters.
+    MalformedDescriptorFragment,
+    /// A base58 address failed to decode and contains no lowercase letters, so it was
+    /// probably uppercased. Base58 is case-sensitive and this cannot be corrected.
+    Base58UppercaseSuspected,
+    /// A custom bech32 human-readable part is empty, too long, or not lowercase alphanumeric.
+    InvalidHrp(String),
+    /// The string does not name any [`AddressType`].
+    UnknownAddressType(String),
+    /// The address string is longer than [`MAX_ADDRESS_LEN`] and was rejected before decoding.
+    AddressTooLong(usize),
+    /// The address is not valid for the network it is required to be used on.
+    WrongNetwork {
+        /// The network the address was required to be valid for
+        required: Network,
+        /// The network the address was parsed as
+        found: Network,
+    },
+    /// The address uses a witness version which cannot be spent under current consensus rules.
+    UnsupportedWitnessVersion(WitnessVersion),
+    /// The script to wrap in P2SH is itself a witness program.
+    ScriptIsWitnessProgram,
+    /// The address string starts with a URI scheme, e.g. `bitcoincash:`, which is not part of
+    /// a Bitcoin address.
+    UnsupportedAddressScheme(String),
 }
 
 impl fmt::Display for Error {
@@ -93,17 +131,51 @@ impl fmt::Display for Error {
             Error::InvalidWitnessVersion(v) => write!(f, "invalid witness script version: {}", v),
             Error::UnparsableWitnessVersion(_) => write!(f, "incorrect format of a witness version byte"),
             Error::MalformedWitnessVersion => f.write_str("bitcoin script opcode does not match any known witness version, the script is malformed"),
-            Error::InvalidWitnessProgramLength(l) => write!(f,
-                "the witness program must be between 2 and 40 bytes in length: length={}", l,
+            Error::WitnessProgramTooShort(l) => write!(f,
+                "the witness program must be at least 2 bytes in length: length={}", l,
+            ),
+            Error::WitnessProgramTooLong(l) => write!(f,
+                "the witness program must be at most 40 bytes in length: length={}", l,
             ),
             Error::InvalidSegwitV0ProgramLength(l) => write!(f,
                 "a v0 witness program must be either of length 20 or 32 bytes: length={}", l,
             ),
+            Error::InvalidSegwitV1ProgramLength(l) => write!(f,
+                "a v1 witness program must be either of length 32 or 2 bytes: length={}", l,
+            ),
             Error::UncompressedPubkey => write!(f,
                 "an uncompressed pubkey was used where it is not allowed",
             ),
             Error::ExcessiveScriptSize => write!(f,
-                "Script size exceed 520 bytes")
+                "Script size exceed 520 bytes"),
+            Error::InvalidHashLength(l) => write!(f,
+                "the address payload has an invalid hash length: length={}", l,
+            ),
+            Error::UnrecognizedScript => write!(f, "script is not of the expected output type"),
+            Error::MalformedDescriptorFragment => write!(f, "malformed addr() descriptor fragment"),
+            Error::InvalidHrp(ref hrp) => write!(f, "invalid bech32 human-readable part: {}", hrp),
+            Error::WrongNetwork { required, found } => write!(f,
+                "address for network {} is not valid for required network {}", found, required,
+            ),
+            Error::AddressTooLong(l) => write!(f,
+                "the address string is too long: length={}, maximum={}", l, MAX_ADDRESS_LEN,
+            ),
+            Error::UnsupportedWitnessVersion(v) => write!(f, "unsupported witness version: {}", v),
+            Error::ScriptIsWitnessProgram => write!(f, "script to wrap in P2SH is a witness program"),
+            Error::UnsupportedAddressScheme(ref scheme) => write!(f, "unsupported address scheme: {}:", scheme),
+            Error::UnknownAddressType(ref s) => {
+                write!(f, "unknown address type '{}', expected one of ", s)?;
+                for (i, address_type) in AddressType::all().iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", address_type)?;
+                }
+                Ok(())
+            }
+            Error::Base58UppercaseSuspected => write!(f,
+                "base58 address appears to have been uppercased; base58 is case-sensitive",
+            ),
         }
     }
 }
@@ -135,6 +207,49 @@ impl From<bech32::Error> for Error {
     }
 }
 
+#[doc(hidden)]
+impl From<hashes::Error> for Error {
+    fn from(e: hashes::Error) -> Error {
+        match e {
+            hashes::Error::InvalidLength(_, got) => Error::InvalidHashLength(got),
+        }
+    }
+}
+
+impl Error {
+    /// Returns whether the error is caused by malformed input which the user can correct,
+    /// e.g. a mistyped address string, as opposed to a structural error where the given key
+    /// or script cannot be turned into an address at all.
+    pub fn is_recoverable(&self) -> bool {
+        match *self {
+            Error::Base58(_)
+            | Error::Bech32(_)
+            | Error::EmptyBech32Payload
+            | Error::InvalidBech32Variant { .. }
+            | Error::InvalidWitnessVersion(_)
+            | Error::UnparsableWitnessVersion(_)
+            | Error::MalformedWitnessVersion
+            | Error::WitnessProgramTooShort(_)
+            | Error::WitnessProgramTooLong(_)
+            | Error::InvalidSegwitV0ProgramLength(_)
+            | Error::InvalidSegwitV1ProgramLength(_)
+            | Error::InvalidHashLength(_)
+            | Error::MalformedDescriptorFragment
+            | Error::Base58UppercaseSuspected
+            | Error::InvalidHrp(_)
+            | Error::UnknownAddressType(_)
+            | Error::AddressTooLong(_)
+            | Error::WrongNetwork { .. }
+            | Error::UnsupportedWitnessVersion(_)
+            | Error::UnsupportedAddressScheme(_) => true,
+            Error::UncompressedPubkey
+            | Error::ExcessiveScriptSize
+            | Error::UnrecognizedScript
+            | Error::ScriptIsWitnessProgram => false,
+        }
+    }
+}
+
 /// The different types of addresses.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum AddressType {
@@ -163,16 +278,20 @@ impl fmt::Display for AddressType {
 }
 
 impl FromStr for AddressType {
-    type Err = ();
+    type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "p2pkh" => Ok(AddressType::P2pkh),
-            "p2sh" => Ok(AddressType::P2sh),
-            "p2wpkh" => Ok(AddressType::P2wpkh),
-            "p2wsh" => Ok(AddressType::P2wsh),
-            "p2tr" => Ok(AddressType::P2tr),
-            _ => Err(()),
-        }
+        AddressType::all()
+            .iter()
+            .find(|address_type| address_type.to_string() == s)
+            .cloned()
+            .ok_or_else(|| Error::UnknownAddressType(s.to_owned()))
+    }
+}
+
+impl AddressType {
+    /// Returns all address types, in the order they are declared.
+    pub fn all() -> [AddressType; 5] {
+        [AddressType::P2pkh, AddressType::P2sh, AddressType::P2wpkh, AddressType::P2wsh, AddressType::P2tr]
     }
 }
 
@@ -241,6 +360,18 @@ impl FromStr for WitnessVersion {
 }
 
 impl WitnessVersion {
+    /// Returns all witness versions, from [`WitnessVersion::V0`] to [`WitnessVersion::V16`], in
+    /// ascending order.
+    pub fn all() -> [WitnessVersion; 17] {
+        [
+            WitnessVersion::V0, WitnessVersion::V1, WitnessVersion::V2, WitnessVersion::V3,
+            WitnessVersion::V4, WitnessVersion::V5, WitnessVersion::V6, WitnessVersion::V7,
+            WitnessVersion::V8, WitnessVersion::V9, WitnessVersion::V10, WitnessVersion::V11,
+            WitnessVersion::V12, WitnessVersion::V13, WitnessVersion::V14, WitnessVersion::V15,
+            WitnessVersion::V16,
+        ]
+    }
+
     /// Converts 5-bit unsigned integer value matching single symbol from Bech32(m) address encoding
     /// ([`bech32::u5`]) into [`WitnessVersion`] variant.
     ///
@@ -328,6 +459,16 @@ impl WitnessVersion {
         self as u8
     }
 
+    /// Returns the script opcode signifying this witness version (`OP_0`..`OP_16`).
+    ///
+    /// This is the inverse of [`WitnessVersion::from_opcode`].
+    pub fn to_opcode(self) -> opcodes::All {
+        match self {
+            WitnessVersion::V0 => opcodes::all::OP_PUSHBYTES_0,
+            no => opcodes::All::from(opcodes::all::OP_PUSHNUM_1.into_u8() + no.into_num() - 1)
+        }
+    }
+
     /// Determine the checksum variant. See BIP-0350 for specification.
     pub fn bech32_variant(&self) -> bech32::Variant {
         match self {
@@ -335,6 +476,24 @@ impl WitnessVersion {
             _ => bech32::Variant::Bech32m,
         }
     }
+
+    /// Returns the following witness version, or `None` for [`WitnessVersion::V16`].
+    pub fn next(self) -> Option<WitnessVersion> {
+        WitnessVersion::from_num(self.into_num() + 1).ok()
+    }
+
+    /// Returns the preceding witness version, or `None` for [`WitnessVersion::V0`].
+    pub fn prev(self) -> Option<WitnessVersion> {
+        match self {
+            WitnessVersion::V0 => None,
+            _ => WitnessVersion::from_num(self.into_num() - 1).ok(),
+        }
+    }
+
+    /// Returns the following witness version, staying at [`WitnessVersion::V16`].
+    pub fn saturating_next(self) -> WitnessVersion {
+        self.next().unwrap_or(WitnessVersion::V16)
+    }
 }
 
 impl From<WitnessVersion> for ::bech32::u5 {
@@ -347,14 +506,14 @@ impl From<WitnessVersion> for ::bech32::u5 {
 impl From<WitnessVersion> for opcodes::All {
     /// Converts [`WitnessVersion`] instance into corresponding Bitcoin scriptopcode (`OP_0`..`OP_16`)
     fn from(version: WitnessVersion) -> opcodes::All {
-        match version {
-            WitnessVersion::V0 => opcodes::all::OP_PUSHBYTES_0,
-            no => opcodes::All::from(opcodes::all::OP_PUSHNUM_1.into_u8() + no.into_num() - 1)
-        }
+        version.to_opcode()
     }
 }
 
 /// The method used to produce an address
+///
+/// Constructing a [`Payload::WitnessProgram`] directly does not validate the program length;
+/// use [`Payload::new_witness_program`] to reject programs that cannot be spent.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Payload {
     /// P2PKH address
@@ -391,6 +550,18 @@ impl Payload {
         })
     }
 
+    /// Creates a witness program payload, checking the program length.
+    ///
+    /// Besides the BIP-141 limit of 2 to 40 bytes, a v0 program must be 20 or 32 bytes long
+    /// and a v1 program 32 bytes (taproot) or 2 bytes (pay-to-anchor).
+    pub fn new_witness_program(version: WitnessVersion, program: Vec<u8>) -> Result<Payload, Error> {
+        check_witness_program(version, &program)?;
+        if version == WitnessVersion::V1 && program.len() != 32 && program.len() != 2 {
+            return Err(Error::InvalidSegwitV1ProgramLength(program.len()));
+        }
+        Ok(Payload::WitnessProgram { version, program })
+    }
+
     /// Generates a script pubkey spending to this [Payload].
     pub fn script_pubkey(&self) -> script::Script {
         match *self {
@@ -404,6 +575,46 @@ impl Payload {
             } => script::Script::new_witness_program(version, prog)
         }
     }
+
+    /// Returns the raw payload bytes: the 20-byte hash for P2PKH and P2SH, the witness program
+    /// otherwise.
+    pub fn as_bytes(&self) -> &[u8] {
+        match *self {
+            Payload::PubkeyHash(ref hash) => &hash[..],
+            Payload::ScriptHash(ref hash) => &hash[..],
+            Payload::WitnessProgram { program: ref prog, .. } => prog,
+        }
+    }
+
+    /// Creates a pay to taproot payload from an already tweaked output key.
+    ///
+    /// The caller guarantees that `output_key` is the tweaked taproot output key; no tweaking
+    /// is done here.
+    pub fn p2tr_from_output_key(output_key: schnorrsig::PublicKey) -> Payload {
+        Payload::WitnessProgram {
+            version: WitnessVersion::V1,
+            program: output_key.serialize().to_vec(),
+        }
+    }
+}
+
+/// A cheap, hashable stand-in for the script pubkey of an [`Address`].
+///
+/// Two addresses have equal keys exactly when they have equal script pubkeys, so this can be
+/// used as a `HashMap` key to group outputs without building a [`script::Script`] for each
+/// one. The network is not part of the key, since the script pubkey does not depend on it.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum ScriptPubkeyKey {
+    /// P2PKH script pubkey
+    PubkeyHash(PubkeyHash),
+    /// P2SH script pubkey
+    ScriptHash(ScriptHash),
+    /// Witness program of 20 bytes, such as P2WPKH
+    WitnessProgram20(WitnessVersion, [u8; 20]),
+    /// Witness program of 32 bytes, such as P2WSH and P2TR
+    WitnessProgram32(WitnessVersion, [u8; 32]),
+    /// Witness program of any other length
+    OtherWitnessProgram(WitnessVersion, Vec<u8>),
 }
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -443,6 +654,33 @@ impl Address {
         })
     }
 
+    /// Creates a pay to script hash P2SH address from a script, like [`Address::p2sh`], but
+    /// refuses to wrap a witness program.
+    ///
+    /// Hashing a witness program script pubkey as a redeem script is almost always a mistake
+    /// for a P2SH-wrapped segwit address, which should be created with [`Address::p2shwpkh`]
+    /// or [`Address::p2shwsh`] from the key or witness script instead. Errors with
+    /// [`Error::ScriptIsWitnessProgram`] in that case.
+    pub fn p2sh_checked(script: &script::Script, network: Network) -> Result<Address, Error> {
+        if script.is_witness_program() {
+            return Err(Error::ScriptIsWitnessProgram);
+        }
+        Address::p2sh(script, network)
+    }
+
+    /// Creates a pay to script hash P2SH address from an already computed script hash,
+    /// e.g. from a watch-only import.
+    ///
+    /// Unlike [`Address::p2sh`], the [`MAX_SCRIPT_ELEMENT_SIZE`] limit on the redeem script
+    /// cannot be enforced here since the script is not known.
+    #[inline]
+    pub fn p2sh_from_hash(hash: ScriptHash, network: Network) -> Address {
+        Address {
+            network,
+            payload: Payload::ScriptHash(hash),
+        }
+    }
+
     /// Create a witness pay to public key address from a public key
     /// This is the native segwit address type for an output redeemable with a single signature
     ///
@@ -464,11 +702,39 @@ impl Address {
         })
     }
 
+    /// Create a witness pay to public key address from an already computed witness public key
+    /// hash, e.g. from a watch-only import.
+    ///
+    /// Unlike [`Address::p2wpkh`] this cannot fail: the key must have been compressed for the
+    /// hash to be a valid witness public key hash.
+    pub fn p2wpkh_from_hash(hash: WPubkeyHash, network: Network) -> Address {
+        Address {
+            network,
+            payload: Payload::WitnessProgram {
+                version: WitnessVersion::V0,
+                program: hash[..].to_vec(),
+            },
+        }
+    }
+
     /// Create a pay to script address that embeds a witness pay to public key
     /// This is a segwit address type that looks familiar (as p2sh) to legacy clients
     ///
     /// Will only return an Error when an uncompressed public key is provided.
     pub fn p2shwpkh(pk: &ecdsa::PublicKey, network: Network) -> Result<Address, Error> {
+        let redeem_script = Address::p2sh_wpkh_redeem_script(pk)?;
+
+        Ok(Address {
+            network: network,
+            payload: Payload::ScriptHash(ScriptHash::hash(redeem_script.as_bytes())),
+        })
+    }
+
+    /// Returns the redeem script (`OP_0 <20-byte key hash>`) of the p2sh-wrapped p2wpkh
+    /// address of `pk`, needed in the scriptSig when spending from it.
+    ///
+    /// Will only return an Error when an uncompressed public key is provided.
+    pub fn p2sh_wpkh_redeem_script(pk: &ecdsa::PublicKey) -> Result<script::Script, Error> {
         if !pk.compressed {
             return Err(Error::UncompressedPubkey);
         }
@@ -476,14 +742,10 @@ impl Address {
         let mut hash_engine = WPubkeyHash::engine();
         pk.write_into(&mut hash_engine).expect("engines don't error");
 
-        let builder = script::Builder::new()
+        Ok(script::Builder::new()
             .push_int(0)
-            .push_slice(&WPubkeyHash::from_engine(hash_engine)[..]);
-
-        Ok(Address {
-            network: network,
-            payload: Payload::ScriptHash(ScriptHash::hash(builder.into_script().as_bytes())),
-        })
+            .push_slice(&WPubkeyHash::from_engine(hash_engine)[..])
+            .into_script())
     }
 
     /// Create a witness pay to script hash address
@@ -497,6 +759,47 @@ impl Address {
         }
     }
 
+    /// Create a witness pay to script hash address from an already computed witness script
+    /// hash, e.g. from a watch-only import.
+    pub fn p2wsh_from_hash(hash: WScriptHash, network: Network) -> Address {
+        Address {
+            network,
+            payload: Payload::WitnessProgram {
+                version: WitnessVersion::V0,
+                program: hash[..].to_vec(),
+            },
+        }
+    }
+
+    /// Creates an address of the given type from its raw hash or witness program bytes, e.g.
+    /// as stored in a database alongside a type tag.
+    ///
+    /// The bytes must be 20 long for P2PKH, P2SH and P2WPKH and 32 long for P2WSH and P2TR,
+    /// otherwise this errors with [`Error::InvalidHashLength`]. For P2TR the bytes are used as
+    /// the output key without checking that they are a valid x-only public key.
+    pub fn from_hash_bytes(bytes: &[u8], kind: AddressType, network: Network) -> Result<Address, Error> {
+        let expected_len = match kind {
+            AddressType::P2pkh | AddressType::P2sh | AddressType::P2wpkh => 20,
+            AddressType::P2wsh | AddressType::P2tr => 32,
+        };
+        if bytes.len() != expected_len {
+            return Err(Error::InvalidHashLength(bytes.len()));
+        }
+        Ok(match kind {
+            AddressType::P2pkh => Address {
+                network,
+                payload: Payload::PubkeyHash(PubkeyHash::from_slice(bytes)?),
+            },
+            AddressType::P2sh => Address::p2sh_from_hash(ScriptHash::from_slice(bytes)?, network),
+            AddressType::P2wpkh => Address::p2wpkh_from_hash(WPubkeyHash::from_slice(bytes)?, network),
+            AddressType::P2wsh => Address::p2wsh_from_hash(WScriptHash::from_slice(bytes)?, network),
+            AddressType::P2tr => Address {
+                network,
+                payload: Payload::WitnessProgram { version: WitnessVersion::V1, program: bytes.to_vec() },
+            },
+        })
+    }
+
     /// Create a pay to script address that embeds a witness pay to script hash address
     /// This is a segwit address type that looks familiar (as p2sh) to legacy clients
     pub fn p2shwsh(script: &script::Script, network: Network) -> Address {
@@ -511,17 +814,123 @@ impl Address {
         }
     }
 
+    /// Returns every standard single-key address of the public key: p2pkh, p2wpkh and
+    /// p2sh-wrapped p2wpkh, the latter reported as [`AddressType::P2sh`].
+    ///
+    /// The segwit forms are skipped for uncompressed keys, for which they cannot be created.
+    pub fn all_address_types(pk: &ecdsa::PublicKey, network: Network) -> Vec<(AddressType, Address)> {
+        let mut addresses = vec![(AddressType::P2pkh, Address::p2pkh(pk, network))];
+        if let Ok(addr) = Address::p2wpkh(pk, network) {
+            addresses.push((AddressType::P2wpkh, addr));
+        }
+        if let Ok(addr) = Address::p2shwpkh(pk, network) {
+            addresses.push((AddressType::P2sh, addr));
+        }
+        addresses
+    }
+
     /// Create a pay to taproot address
     pub fn p2tr(taptweaked_key: schnorrsig::PublicKey, network: Network) -> Address {
         Address {
             network: network,
-            payload: Payload::WitnessProgram {
-                version: WitnessVersion::V1,
-                program: taptweaked_key.serialize().to_vec()
-            }
+            payload: Payload::p2tr_from_output_key(taptweaked_key),
+        }
+    }
+
+    /// Create a pay to taproot address committing to a script tree with a single leaf.
+    ///
+    /// The leaf uses the tapscript leaf version (0xc0); since the tree has only one leaf, the
+    /// merkle root is the leaf hash itself, which is then used to tweak `internal_key`.
+    pub fn p2tr_single_leaf<C: Verification>(
+        secp: &Secp256k1<C>,
+        internal_key: schnorrsig::PublicKey,
+        leaf_script: &script::Script,
+        network: Network,
+    ) -> Address {
+        let mut leaf_engine = TapLeafHash::engine();
+        leaf_engine.input(&[0xc0]);
+        leaf_script.consensus_encode(&mut leaf_engine).expect("engines don't error");
+        let merkle_root = TapLeafHash::from_engine(leaf_engine);
+
+        Address::p2tr(tap_tweak(secp, internal_key, &merkle_root[..]), network)
+    }
+
+    /// Create a pay to taproot address that can only be spent through the script tree with
+    /// the given merkle root.
+    ///
+    /// The internal key is [`NUMS_INTERNAL_KEY`], for which nobody knows the private key, so
+    /// the key path is unspendable.
+    pub fn p2tr_script_only<C: Verification>(
+        secp: &Secp256k1<C>,
+        merkle_root: TapBranchHash,
+        network: Network,
+    ) -> Address {
+        let internal_key = schnorrsig::PublicKey::from_slice(&NUMS_INTERNAL_KEY)
+            .expect("NUMS point is a valid x-only key");
+        Address::p2tr(tap_tweak(secp, internal_key, &merkle_root[..]), network)
+    }
+
+    /// Create a segwit address from a witness version and program.
+    ///
+    /// Errors if the program length is invalid for the version, see
+    /// [`Payload::new_witness_program`].
+    pub fn from_witness_program(version: WitnessVersion, program: Vec<u8>, network: Network) -> Result<Address, Error> {
+        Ok(Address {
+            network,
+            payload: Payload::new_witness_program(version, program)?,
+        })
+    }
+
+    /// Returns a builder creating addresses of any type for the given network.
+    pub fn builder(network: Network) -> AddressBuilder {
+        AddressBuilder { network }
+    }
+
+    /// Splits the address into its payload and network.
+    pub fn into_parts(self) -> (Payload, Network) {
+        (self.payload, self.network)
+    }
+
+    /// Creates an address from its payload and network, the inverse of [`Address::into_parts`].
+    ///
+    /// Unlike setting the public fields directly, this checks that a witness program payload
+    /// satisfies the BIP-141 length limits, so that the address can be encoded and parsed back.
+    pub fn from_parts(payload: Payload, network: Network) -> Result<Address, Error> {
+        if let Payload::WitnessProgram { version, program: ref prog } = payload {
+            check_witness_program(version, prog)?;
+        }
+        Ok(Address { network, payload })
+    }
+
+    /// Returns the network on which this address is usable.
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    /// Returns the address with the same payload on another network.
+    ///
+    /// The encoding follows the network: the base58 version byte of legacy addresses and the
+    /// human-readable part of segwit addresses change accordingly.
+    pub fn with_network(&self, network: Network) -> Address {
+        Address {
+            network,
+            payload: self.payload.clone(),
         }
     }
 
+    /// Moves each address to `target` with [`Address::with_network`], keeping the order.
+    pub fn migrate_network(addrs: &[Address], target: Network) -> Vec<Address> {
+        addrs.iter().map(|addr| addr.with_network(target)).collect()
+    }
+
+    /// Returns the p2p message magic of the network of this address, see [`Network::magic`].
+    ///
+    /// Note that a signet or testnet4 address parsed from a string is reported as
+    /// [`Network::Testnet`], since they share their encodings, and so gets the testnet magic.
+    pub fn p2p_magic(&self) -> u32 {
+        self.network.magic()
+    }
+
     /// Get the address type of the address.
     /// None if unknown, non-standard or related to the future witness version.
     pub fn address_type(&self) -> Option<AddressType> {
@@ -546,6 +955,80 @@ impl Address {
         }
     }
 
+    /// Returns a key to sort addresses by network, then address type, then payload bytes.
+    ///
+    /// The derived [`Ord`] compares the payload first, so that e.g. all P2PKH addresses sort
+    /// before all P2SH ones regardless of network; lists shown to users should be sorted with
+    /// `sort_by_key(Address::sort_key)` instead. Non-standard addresses, whose type is `None`,
+    /// come first within each network.
+    pub fn sort_key(&self) -> (Network, Option<AddressType>, Vec<u8>) {
+        (self.network, self.address_type(), self.payload.as_bytes().to_vec())
+    }
+
+    /// Checks whether this is a version 1 witness program of a length other than 32 bytes.
+    ///
+    /// Such outputs are not pay to taproot, so [`Address::address_type`] returns `None` for
+    /// them, but unlike unassigned witness versions they can be labelled as non-standard v1.
+    pub fn is_nonstandard_taproot(&self) -> bool {
+        match self.payload {
+            Payload::WitnessProgram { version: WitnessVersion::V1, program: ref prog } => prog.len() != 32,
+            _ => false,
+        }
+    }
+
+    /// Returns the bech32 checksum variant implied by the witness version of the address, which
+    /// is the variant [`Address::from_str`] requires when parsing it.
+    ///
+    /// Returns `None` for base58 (non-segwit) addresses.
+    pub fn bech32_variant(&self) -> Option<bech32::Variant> {
+        match self.payload {
+            Payload::WitnessProgram { version, .. } => Some(version.bech32_variant()),
+            Payload::PubkeyHash(_) | Payload::ScriptHash(_) => None,
+        }
+    }
+
+    /// Returns whether this is a segwit address of any witness version.
+    pub fn is_segwit(&self) -> bool {
+        match self.payload {
+            Payload::WitnessProgram { .. } => true,
+            Payload::PubkeyHash(_) | Payload::ScriptHash(_) => false,
+        }
+    }
+
+    /// Returns whether this is a legacy (base58) P2PKH or P2SH address.
+    ///
+    /// Note that P2SH-wrapped segwit addresses are legacy addresses.
+    pub fn is_legacy(&self) -> bool {
+        !self.is_segwit()
+    }
+
+    /// Returns whether this is a pay to taproot address, i.e. a version 1, 32-byte witness
+    /// program.
+    pub fn is_taproot(&self) -> bool {
+        match self.payload {
+            Payload::WitnessProgram { version: WitnessVersion::V1, program: ref prog } => prog.len() == 32,
+            _ => false,
+        }
+    }
+
+    /// Returns whether the string form of this segwit address uses the bech32 variant its
+    /// witness version requires (bech32 for v0, bech32m otherwise), i.e. whether parsing it
+    /// back avoids [`Error::InvalidBech32Variant`].
+    ///
+    /// The address is encoded and the checksum variant is read back from the string, so this
+    /// also returns false if the string cannot be bech32 decoded at all. Always true for legacy
+    /// addresses, which are not bech32 encoded.
+    pub fn has_consistent_bech32_variant(&self) -> bool {
+        match self.payload {
+            Payload::WitnessProgram { version, .. } => {
+                let mut buf = String::new();
+                self.encode_into(&mut buf);
+                has_bech32_variant_of(&buf, version)
+            }
+            Payload::PubkeyHash(_) | Payload::ScriptHash(_) => true,
+        }
+    }
+
     /// Check whether or not the address is following Bitcoin
     /// standardness rules.
     ///
@@ -555,239 +1038,1642 @@ impl Address {
         self.address_type().is_some()
     }
 
-    /// Get an [Address] from an output script (scriptPubkey).
-    pub fn from_script(script: &script::Script, network: Network) -> Option<Address> {
-        Some(Address {
-            payload: Payload::from_script(script)?,
-            network: network,
-        })
+    /// Checks whether funds sent to this address are spendable under the current consensus
+    /// rules, i.e. whether it is a P2PKH, P2SH, P2WPKH, P2WSH or P2TR address.
+    ///
+    /// Outputs to unassigned witness versions or wrong-length programs are anyone-can-spend
+    /// today and so are not safe to send to. This is the same as [`Address::is_standard`] for
+    /// now; the two will differ once pay-to-anchor outputs, which are standard for relay but
+    /// anyone-can-spend, get an address type.
+    pub fn is_spendable_today(&self) -> bool {
+        self.is_standard()
     }
 
-    /// Generates a script pubkey spending to this address
-    pub fn script_pubkey(&self) -> script::Script {
-        self.payload.script_pubkey()
+    /// Returns the pushes a script sig spending an output to this address must contain, in
+    /// order, e.g. for sizing transaction inputs before signing.
+    ///
+    /// Returns `None` for segwit addresses, which are spent with an empty script sig. Note that
+    /// a P2SH address may wrap a segwit program, in which case the redeem script is the only
+    /// push; this cannot be told from the address alone.
+    pub fn scriptsig_template(&self) -> Option<ScriptSigTemplate> {
+        match self.payload {
+            Payload::PubkeyHash(_) => Some(ScriptSigTemplate {
+                pushes: vec![ScriptSigPush::Signature, ScriptSigPush::PublicKey],
+            }),
+            Payload::ScriptHash(_) => Some(ScriptSigTemplate {
+                pushes: vec![ScriptSigPush::RedeemScriptInputs, ScriptSigPush::RedeemScript],
+            }),
+            Payload::WitnessProgram { .. } => None,
+        }
     }
 
-    /// Creates a URI string *bitcoin:address* optimized to be encoded in QR codes.
+    /// Returns the hex of the raw payload bytes, see [`Payload::as_bytes`].
     ///
-    /// If the address is bech32, both the schema and the address become uppercase.
-    /// If the address is base58, the schema is lowercase and the address is left mixed case.
+    /// Unlike [`fmt::Debug`], which prints the encoded address, this is meant for inspecting
+    /// the underlying hash or witness program.
+    pub fn payload_hex(&self) -> String {
+        self.payload.as_bytes().to_hex()
+    }
+
+    /// Returns the pubkey hash of a P2PKH address.
     ///
-    /// Quoting BIP 173 "inside QR codes uppercase SHOULD be used, as those permit the use of
-    /// alphanumeric mode, which is 45% more compact than the normal byte mode."
-    pub fn to_qr_uri(&self) -> String {
-        let schema = match self.payload {
-            Payload::WitnessProgram { .. } => "BITCOIN",
-            _ => "bitcoin",
-        };
-        format!("{}:{:#}", schema, self)
+    /// Returns `None` for every other address type, including P2WPKH: its 20-byte witness
+    /// program is a [`WPubkeyHash`], not a [`PubkeyHash`].
+    pub fn pubkey_hash(&self) -> Option<PubkeyHash> {
+        match self.payload {
+            Payload::PubkeyHash(hash) => Some(hash),
+            _ => None,
+        }
     }
 
-    /// Parsed addresses do not always have *one* network. The problem is that legacy testnet,
-    /// regtest and signet addresse use the same prefix instead of multiple different ones. When
-    /// parsing, such addresses are always assumed to be testnet addresses (the same is true for
-    /// bech32 signet addresses). So if one wants to check if an address belongs to a certain
-    /// network a simple comparison is not enough anymore. Instead this function can be used.
+    /// Returns the script hash of a P2SH address.
     ///
-    /// ```rust
-    /// use bitcoin::{Address, Network};
+    /// Returns `None` for every other address type, including P2WSH whose witness program is
+    /// a [`WScriptHash`].
+    pub fn script_hash(&self) -> Option<ScriptHash> {
+        match self.payload {
+            Payload::ScriptHash(hash) => Some(hash),
+            _ => None,
+        }
+    }
+
+    /// Returns the base58 version byte this address is encoded with on its network.
     ///
-    /// let address: Address = "2N83imGV3gPwBzKJQvWJ7cRUY2SpUyU6A5e".parse().unwrap();
-    /// assert!(address.is_valid_for_network(Network::Testnet));
-    /// assert!(address.is_valid_for_network(Network::Regtest));
-    /// assert!(address.is_valid_for_network(Network::Signet));
+    /// Returns `None` for segwit addresses, which are bech32 encoded and have no version byte.
+    pub fn base58_version_byte(&self) -> Option<u8> {
+        let encoding = self.encoding();
+        match self.payload {
+            Payload::PubkeyHash(_) => Some(encoding.p2pkh_prefix),
+            Payload::ScriptHash(_) => Some(encoding.p2sh_prefix),
+            Payload::WitnessProgram { .. } => None,
+        }
+    }
+
+    /// Returns the data elements a BIP37 bloom filter must contain to match outputs paying to
+    /// this address.
     ///
-    /// assert_eq!(address.is_valid_for_network(Network::Bitcoin), false);
+    /// A BIP37 filter matches an output if any data push in its script pubkey is in the
+    /// filter. For every address type the only non-empty push is the payload: the pubkey hash,
+    /// the script hash or the witness program. Spends of the output are then matched through
+    /// the outpoint, which peers add to the filter themselves when `BLOOM_UPDATE_ALL` is set.
+    pub fn bloom_filter_elements(&self) -> Vec<Vec<u8>> {
+        vec![self.payload.as_bytes().to_vec()]
+    }
+
+    /// Returns the first four bytes of the raw payload, zero-padded on the right for shorter
+    /// witness programs.
     ///
-    /// let address: Address = "32iVBEu4dxkUQk9dJbZUiBiQdmypcEyJRf".parse().unwrap();
-    /// assert!(address.is_valid_for_network(Network::Bitcoin));
-    /// assert_eq!(address.is_valid_for_network(Network::Testnet), false);
-    /// ```
-    pub fn is_valid_for_network(&self, network: Network) -> bool {
-        let is_legacy = match self.address_type() {
-            Some(AddressType::P2pkh) | Some(AddressType::P2sh) => true,
-            _ => false
+    /// This is only a short prefix for indexing, not a checksum: distinct addresses are
+    /// expected to share fingerprints.
+    pub fn payload_fingerprint(&self) -> [u8; 4] {
+        let bytes = self.payload.as_bytes();
+        let len = bytes.len().min(4);
+        let mut fingerprint = [0u8; 4];
+        fingerprint[..len].copy_from_slice(&bytes[..len]);
+        fingerprint
+    }
+
+    /// Returns a shortened form of the address for logs, e.g. `"p2wpkh:bc1q…f3t4"`.
+    ///
+    /// Only the address type, the leading characters fixed by the network and type (the
+    /// human-readable part and witness version, or the base58 version character), and the
+    /// last [`LOG_FINGERPRINT_SUFFIX_LEN`] characters are revealed.
+    pub fn log_fingerprint(&self) -> String {
+        let encoded = self.to_string();
+        let head_len = match self.payload {
+            Payload::WitnessProgram { .. } => encoded.rfind('1').map_or(0, |sep| sep + 2),
+            Payload::PubkeyHash(_) | Payload::ScriptHash(_) => 1,
+        };
+        let tail_start = encoded.len().saturating_sub(LOG_FINGERPRINT_SUFFIX_LEN).max(head_len);
+        let address_type = match self.address_type() {
+            Some(address_type) => address_type.to_string(),
+            None => "unknown".to_owned(),
         };
+        format!("{}:{}\u{2026}{}", address_type, &encoded[..head_len], &encoded[tail_start..])
+    }
+
+    /// Get an [Address] from an output script (scriptPubkey).
+    pub fn from_script(script: &script::Script, network: Network) -> Option<Address> {
+        Some(Address {
+            payload: Payload::from_script(script)?,
+            network: network,
+        })
+    }
+
+    /// Returns the addresses paid to by a sequence of output scripts, e.g. a transaction's
+    /// outputs, together with the index of each output.
+    ///
+    /// Outputs without an address (see [`Address::from_script`]), such as `OP_RETURN`, are
+    /// skipped.
+    pub fn scan_outputs<'a, I>(scripts: I, network: Network) -> impl Iterator<Item = (usize, Address)> + 'a
+    where
+        I: IntoIterator<Item = &'a script::Script>,
+        I::IntoIter: 'a,
+    {
+        scripts
+            .into_iter()
+            .enumerate()
+            .filter_map(move |(i, script)| Address::from_script(script, network).map(|addr| (i, addr)))
+    }
 
-        match (self.network, network) {
-            (a, b) if a == b => true,
-            (Network::Bitcoin, _) | (_, Network::Bitcoin) => false,
-            (Network::Regtest, _) | (_, Network::Regtest) if !is_legacy => false,
-            (Network::Testnet, _) | (Network::Regtest, _) | (Network::Signet, _) => true
+    /// Get the P2PKH [Address] equivalent to a bare pay to public key (P2PK) output script.
+    ///
+    /// P2PK outputs have no address of their own; block explorers conventionally display them
+    /// as the P2PKH address of the same public key.
+    ///
+    /// Errors with [`Error::UnrecognizedScript`] if the script is not a P2PK output.
+    pub fn from_p2pk_script(script: &script::Script, network: Network) -> Result<Address, Error> {
+        if !script.is_p2pk() {
+            return Err(Error::UnrecognizedScript);
         }
+        // strip the leading push opcode and the trailing OP_CHECKSIG
+        let pubkey = &script.as_bytes()[1..script.len() - 1];
+        Ok(Address {
+            network,
+            payload: Payload::PubkeyHash(PubkeyHash::hash(pubkey)),
+        })
     }
-}
 
-// Alternate formatting `{:#}` is used to return uppercase version of bech32 addresses which should
-// be used in QR codes, see [Address::to_qr_uri]
-impl fmt::Display for Address {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        match self.payload {
-            Payload::PubkeyHash(ref hash) => {
-                let mut prefixed = [0; 21];
-                prefixed[0] = match self.network {
-                    Network::Bitcoin => PUBKEY_ADDRESS_PREFIX_MAIN,
-                    Network::Testnet | Network::Signet | Network::Regtest => PUBKEY_ADDRESS_PREFIX_TEST,
-                };
-                prefixed[1..].copy_from_slice(&hash[..]);
-                base58::check_encode_slice_to_fmt(fmt, &prefixed[..])
-            }
-            Payload::ScriptHash(ref hash) => {
-                let mut prefixed = [0; 21];
-                prefixed[0] = match self.network {
-                    Network::Bitcoin => SCRIPT_ADDRESS_PREFIX_MAIN,
-                    Network::Testnet | Network::Signet | Network::Regtest => SCRIPT_ADDRESS_PREFIX_TEST,
-                };
-                prefixed[1..].copy_from_slice(&hash[..]);
-                base58::check_encode_slice_to_fmt(fmt, &prefixed[..])
+    /// Get the addresses of a bare multisig script (`OP_m <pubkeys> OP_n OP_CHECKMULTISIG`)
+    /// used as a redeem or witness script, along with the P2PKH addresses of its keys.
+    ///
+    /// Errors with [`Error::UnrecognizedScript`] if the script is not a multisig template with
+    /// valid public keys, and with [`Error::ExcessiveScriptSize`] as [`Address::p2sh`] does.
+    pub fn from_multisig_script(script: &script::Script, network: Network) -> Result<MultisigAddresses, Error> {
+        use blockdata::opcodes::all::{OP_CHECKMULTISIG, OP_PUSHNUM_1, OP_PUSHNUM_16};
+
+        fn small_int(instruction: &Instruction) -> Option<usize> {
+            match *instruction {
+                Instruction::Op(op) if op.into_u8() >= OP_PUSHNUM_1.into_u8() && op.into_u8() <= OP_PUSHNUM_16.into_u8() =>
+                    Some((op.into_u8() - OP_PUSHNUM_1.into_u8() + 1) as usize),
+                _ => None,
             }
-            Payload::WitnessProgram {
-                version,
-                program: ref prog,
-            } => {
-                let hrp = match self.network {
-                    Network::Bitcoin => "bc",
-                    Network::Testnet | Network::Signet => "tb",
-                    Network::Regtest => "bcrt",
-                };
-                let mut upper_writer;
-                let writer = if fmt.alternate() {
-                    upper_writer = UpperWriter(fmt);
-                    &mut upper_writer as &mut dyn fmt::Write
-                } else {
-                    fmt as &mut dyn fmt::Write
-                };
-                let mut bech32_writer = bech32::Bech32Writer::new(hrp, version.bech32_variant(), writer)?;
-                bech32::WriteBase32::write_u5(&mut bech32_writer, version.into())?;
-                bech32::ToBase32::write_base32(&prog, &mut bech32_writer)
+        }
+
+        let instructions = script
+            .instructions()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| Error::UnrecognizedScript)?;
+        if instructions.len() < 4 || instructions[instructions.len() - 1] != Instruction::Op(OP_CHECKMULTISIG) {
+            return Err(Error::UnrecognizedScript);
+        }
+        let keys = &instructions[1..instructions.len() - 2];
+        let required = small_int(&instructions[0]).ok_or(Error::UnrecognizedScript)?;
+        let total = small_int(&instructions[instructions.len() - 2]).ok_or(Error::UnrecognizedScript)?;
+        if total != keys.len() || required > total {
+            return Err(Error::UnrecognizedScript);
+        }
+
+        let p2pkh = keys
+            .iter()
+            .map(|instruction| match *instruction {
+                Instruction::PushBytes(bytes) => ecdsa::PublicKey::from_slice(bytes)
+                    .map(|pk| Address::p2pkh(&pk, network))
+                    .map_err(|_| Error::UnrecognizedScript),
+                Instruction::Op(_) => Err(Error::UnrecognizedScript),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(MultisigAddresses {
+            p2sh: Address::p2sh(script, network)?,
+            p2wsh: Address::p2wsh(script, network),
+            p2shwsh: Address::p2shwsh(script, network),
+            p2pkh,
+        })
+    }
+
+    /// Parses an address either given bare or wrapped in an `addr(...)` output descriptor.
+    ///
+    /// Errors with [`Error::MalformedDescriptorFragment`] if the wrapper is not closed by the
+    /// last character of the string.
+    pub fn from_descriptor_fragment(s: &str) -> Result<Address, Error> {
+        const OPENING: &str = "addr(";
+
+        if !s.starts_with(OPENING) {
+            return Address::from_str(s);
+        }
+        let inner = &s[OPENING.len()..];
+        if !inner.ends_with(')') {
+            return Err(Error::MalformedDescriptorFragment);
+        }
+        Address::from_str(&inner[..inner.len() - 1])
+    }
+
+    /// Checks whether `s` is a valid address, running the same checks as [`Address::from_str`]
+    /// but discarding the parsed address.
+    ///
+    /// This is intended for form validation. The witness program is checked on the decoded
+    /// 5-bit data without being converted to bytes, and no [`Payload`] is built.
+    pub fn validate(s: &str) -> Result<(), Error> {
+        if s.len() > MAX_ADDRESS_LEN {
+            return Err(Error::AddressTooLong(s.len()));
+        }
+        if let Some(scheme) = find_uri_scheme(s) {
+            return Err(Error::UnsupportedAddressScheme(scheme.to_owned()));
+        }
+
+        if parse_hrp(find_bech32_prefix(s)).is_some() {
+            return validate_bech32_payload(s);
+        }
+
+        let data = decode_base58_data(s)?;
+        match data[0] {
+            PUBKEY_ADDRESS_PREFIX_MAIN
+            | SCRIPT_ADDRESS_PREFIX_MAIN
+            | PUBKEY_ADDRESS_PREFIX_TEST
+            | SCRIPT_ADDRESS_PREFIX_TEST => Ok(()),
+            x => Err(Error::Base58(base58::Error::InvalidAddressVersion(x))),
+        }
+    }
+
+    /// Returns every problem found with the address string `s`, or an empty vector if it is a
+    /// valid address.
+    ///
+    /// Unlike [`Address::from_str`], which stops at the first error, the checksum, witness
+    /// version, program length and bech32 variant of a segwit address are checked
+    /// independently of each other. This is meant for diagnostics and is slower than parsing.
+    pub fn diagnose(s: &str) -> Vec<Error> {
+        let mut errors = Vec::new();
+        if s.len() > MAX_ADDRESS_LEN {
+            errors.push(Error::AddressTooLong(s.len()));
+            return errors;
+        }
+
+        if parse_hrp(find_bech32_prefix(s)).is_some() {
+            diagnose_bech32_payload(s, &mut errors);
+        } else if let Err(e) = Address::from_str(s) {
+            errors.push(e);
+        }
+        errors
+    }
+
+    /// Parses an address pasted from a file or spreadsheet.
+    ///
+    /// A single leading UTF-8 byte order mark (`U+FEFF`) is removed, then leading and trailing
+    /// ASCII whitespace (including `\r\n`) is trimmed, before parsing with [`Address::from_str`].
+    /// Nothing else is altered; `from_str` itself stays strict.
+    pub fn from_trimmed_str(s: &str) -> Result<Address, Error> {
+        let s = if s.starts_with('\u{FEFF}') { &s['\u{FEFF}'.len_utf8()..] } else { s };
+        Address::from_str(s.trim_matches(|c: char| c.is_ascii_whitespace()))
+    }
+
+    /// Parses an address like [`Address::from_str`], but rejects segwit addresses with a
+    /// witness version above [`WitnessVersion::V1`] with [`Error::UnsupportedWitnessVersion`].
+    ///
+    /// Outputs to such addresses are anyone-can-spend until a soft fork assigns meaning to the
+    /// version, so applications may want to refuse them up front.
+    pub fn from_str_rejecting_future(s: &str) -> Result<Address, Error> {
+        let address = Address::from_str(s)?;
+        match address.payload {
+            Payload::WitnessProgram { version, .. } if version > WitnessVersion::V1 => {
+                Err(Error::UnsupportedWitnessVersion(version))
             }
+            _ => Ok(address),
         }
     }
-}
 
-struct UpperWriter<W: fmt::Write>(W);
+    /// Parses one address per line, e.g. from a CSV import.
+    ///
+    /// Each line is trimmed of surrounding ASCII whitespace and lines left empty are skipped.
+    /// Errors are returned together with the zero-based index of the offending line.
+    pub fn parse_many<'a>(lines: impl Iterator<Item = &'a str>) -> Vec<Result<Address, (usize, Error)>> {
+        lines
+            .enumerate()
+            .map(|(i, line)| (i, line.trim_matches(|c: char| c.is_ascii_whitespace())))
+            .filter(|&(_, line)| !line.is_empty())
+            .map(|(i, line)| Address::from_str(line).map_err(|e| (i, e)))
+            .collect()
+    }
 
-impl<W: fmt::Write> fmt::Write for UpperWriter<W> {
-    fn write_str(&mut self, s: &str) -> fmt::Result {
-        for c in s.chars() {
-            self.0.write_char(c.to_ascii_uppercase())?;
+    /// Returns whether both addresses have the same payload, ignoring their networks, i.e.
+    /// whether they pay to the same script pubkey.
+    ///
+    /// Note that a P2SH-wrapped segwit address and the native segwit address for the same key
+    /// have different payloads.
+    pub fn same_payload(&self, other: &Address) -> bool {
+        self.payload == other.payload
+    }
+
+    /// Returns whether this is the P2SH-wrapped P2WPKH (p2sh-p2wpkh) address of `pubkey`.
+    ///
+    /// A P2SH address wrapping a segwit program cannot be told apart from any other P2SH address
+    /// without knowing the redeem script, which this reconstructs from the key.
+    pub fn is_wrapped_segwit_for_pubkey(&self, pubkey: &ecdsa::PublicKey) -> bool {
+        match Address::p2shwpkh(pubkey, self.network) {
+            Ok(wrapped) => wrapped.payload == self.payload,
+            Err(_) => false,
         }
-        Ok(())
     }
-}
 
-/// Extract the bech32 prefix.
-/// Returns the same slice when no prefix is found.
-fn find_bech32_prefix(bech32: &str) -> &str {
-    // Split at the last occurrence of the separator character '1'.
-    match bech32.rfind('1') {
-        None => bech32,
-        Some(sep) => bech32.split_at(sep).0,
+    /// Returns whether this address pays to `pubkey`.
+    ///
+    /// Matches P2PKH, p2sh-p2wpkh and P2WPKH addresses of the key, and P2TR addresses whose
+    /// output key is either the x-only form of the key or its BIP-86 key path only tweak, which
+    /// is how wallets derive single key taproot addresses.
+    pub fn is_related_to_pubkey<C: Verification>(&self, secp: &Secp256k1<C>, pubkey: &ecdsa::PublicKey) -> bool {
+        match self.payload {
+            Payload::PubkeyHash(ref hash) => *hash == pubkey.pubkey_hash(),
+            Payload::ScriptHash(_) => self.is_wrapped_segwit_for_pubkey(pubkey),
+            Payload::WitnessProgram { version: WitnessVersion::V0, program: ref prog } => {
+                match pubkey.wpubkey_hash() {
+                    Some(hash) => hash[..] == prog[..],
+                    None => false,
+                }
+            }
+            Payload::WitnessProgram { version: WitnessVersion::V1, program: ref prog } if prog.len() == 32 => {
+                let xonly = schnorrsig::PublicKey::from_slice(&pubkey.key.serialize()[1..])
+                    .expect("x coordinate of a valid key");
+                xonly.serialize()[..] == prog[..] || tap_tweak(secp, xonly, &[]).serialize()[..] == prog[..]
+            }
+            Payload::WitnessProgram { .. } => false,
+        }
     }
-}
 
-impl FromStr for Address {
-    type Err = Error;
+    /// Returns the index of the first key in `pubkeys` this address pays to, as determined by
+    /// [`Address::is_related_to_pubkey`].
+    pub fn is_related_to_any_pubkey<C: Verification>(
+        &self,
+        secp: &Secp256k1<C>,
+        pubkeys: &[ecdsa::PublicKey],
+    ) -> Option<usize> {
+        pubkeys.iter().position(|pk| self.is_related_to_pubkey(secp, pk))
+    }
 
-    fn from_str(s: &str) -> Result<Address, Error> {
-        // try bech32
-        let bech32_network = match find_bech32_prefix(s) {
-            // note that upper or lowercase is allowed but NOT mixed case
-            "bc" | "BC" => Some(Network::Bitcoin),
-            "tb" | "TB" => Some(Network::Testnet), // this may also be signet
-            "bcrt" | "BCRT" => Some(Network::Regtest),
+    /// Returns the taproot output key of a pay to taproot address.
+    ///
+    /// Returns `None` unless the payload is a version 1, 32-byte witness program holding a
+    /// valid x-only public key.
+    pub fn taproot_output_key(&self) -> Option<schnorrsig::PublicKey> {
+        match self.payload {
+            Payload::WitnessProgram { version: WitnessVersion::V1, program: ref prog } if prog.len() == 32 => {
+                schnorrsig::PublicKey::from_slice(prog).ok()
+            }
             _ => None,
-        };
-        if let Some(network) = bech32_network {
-            // decode as bech32
-            let (_, payload, variant) = bech32::decode(s)?;
-            if payload.is_empty() {
-                return Err(Error::EmptyBech32Payload);
+        }
+    }
+
+    /// Returns the script pubkey of a pay to taproot address (`OP_1 <32-byte key>`) as a fixed
+    /// size array, avoiding the allocation of a [`script::Script`].
+    ///
+    /// Returns `None` unless the payload is a version 1, 32-byte witness program. The key is
+    /// not checked to be a valid x-only public key.
+    pub fn taproot_spk_array(&self) -> Option<[u8; 34]> {
+        match self.payload {
+            Payload::WitnessProgram { version: WitnessVersion::V1, program: ref prog } if prog.len() == 32 => {
+                let mut spk = [0u8; 34];
+                spk[0] = opcodes::all::OP_PUSHNUM_1.into_u8();
+                spk[1] = opcodes::all::OP_PUSHBYTES_32.into_u8();
+                spk[2..].copy_from_slice(prog);
+                Some(spk)
             }
+            _ => None,
+        }
+    }
 
-            // Get the script version and program (converted from 5-bit to 8-bit)
-            let (version, program): (WitnessVersion, Vec<u8>) = {
-                let (v, p5) = payload.split_at(1);
-                (WitnessVersion::from_u5(v[0])?, bech32::FromBase32::from_base32(p5)?)
-            };
+    /// Generates a script pubkey spending to this address
+    pub fn script_pubkey(&self) -> script::Script {
+        self.payload.script_pubkey()
+    }
 
-            if program.len() < 2 || program.len() > 40 {
-                return Err(Error::InvalidWitnessProgramLength(program.len()));
-            }
+    /// Returns the raw bytes of the script pubkey spending to this address, built from the
+    /// payload without going through [`script::Script`].
+    pub fn script_pubkey_bytes(&self) -> Vec<u8> {
+        use blockdata::opcodes::all::*;
 
-            // Specific segwit v0 check.
-            if version == WitnessVersion::V0 && (program.len() != 20 && program.len() != 32) {
-                return Err(Error::InvalidSegwitV0ProgramLength(program.len()));
+        let mut bytes = Vec::with_capacity(self.script_pubkey_len());
+        match self.payload {
+            Payload::PubkeyHash(ref hash) => {
+                bytes.extend_from_slice(&[OP_DUP.into_u8(), OP_HASH160.into_u8(), OP_PUSHBYTES_20.into_u8()]);
+                bytes.extend_from_slice(&hash[..]);
+                bytes.extend_from_slice(&[OP_EQUALVERIFY.into_u8(), OP_CHECKSIG.into_u8()]);
+            }
+            Payload::ScriptHash(ref hash) => {
+                bytes.extend_from_slice(&[OP_HASH160.into_u8(), OP_PUSHBYTES_20.into_u8()]);
+                bytes.extend_from_slice(&hash[..]);
+                bytes.push(OP_EQUAL.into_u8());
             }
+            Payload::WitnessProgram { version, program: ref prog } => {
+                // programs are at most 40 bytes, so the push opcode is the length itself
+                bytes.extend_from_slice(&[version.to_opcode().into_u8(), prog.len() as u8]);
+                bytes.extend_from_slice(prog);
+            }
+        }
+        bytes
+    }
 
-            // Encoding check
-            let expected = version.bech32_variant();
-            if expected != variant {
-                return Err(Error::InvalidBech32Variant { expected, found: variant });
+    /// Checks whether the raw script pubkey `spk` pays to this address, comparing it in place
+    /// against the script pubkey implied by the payload.
+    pub fn matches_script_pubkey_bytes(&self, spk: &[u8]) -> bool {
+        use blockdata::opcodes::all::*;
+
+        match self.payload {
+            Payload::PubkeyHash(ref hash) => {
+                spk.len() == 25
+                    && spk[..3] == [OP_DUP.into_u8(), OP_HASH160.into_u8(), OP_PUSHBYTES_20.into_u8()]
+                    && spk[3..23] == hash[..]
+                    && spk[23..] == [OP_EQUALVERIFY.into_u8(), OP_CHECKSIG.into_u8()]
+            }
+            Payload::ScriptHash(ref hash) => {
+                spk.len() == 23
+                    && spk[..2] == [OP_HASH160.into_u8(), OP_PUSHBYTES_20.into_u8()]
+                    && spk[2..22] == hash[..]
+                    && spk[22] == OP_EQUAL.into_u8()
+            }
+            Payload::WitnessProgram { version, program: ref prog } => {
+                spk.len() == 2 + prog.len()
+                    && spk[0] == version.to_opcode().into_u8()
+                    && spk[1] as usize == prog.len()
+                    && spk[2..] == prog[..]
             }
+        }
+    }
 
-            return Ok(Address {
-                payload: Payload::WitnessProgram {
-                    version: version,
-                    program: program,
-                },
-                network: network,
-            });
+    /// Returns the instructions of the script pubkey spending to this address, built from the
+    /// payload without serializing the script.
+    ///
+    /// The instructions are the same as those yielded by parsing [`Address::script_pubkey`],
+    /// in particular witness version 0 is yielded as an empty push.
+    pub fn script_pubkey_instructions(&self) -> ScriptPubkeyInstructions<'_> {
+        ScriptPubkeyInstructions { payload: &self.payload, index: 0 }
+    }
+
+    /// Returns the length in bytes of the script pubkey spending to this address, computed
+    /// from the payload without building the script.
+    pub fn script_pubkey_len(&self) -> usize {
+        match self.payload {
+            // OP_DUP OP_HASH160 <20-byte push> OP_EQUALVERIFY OP_CHECKSIG
+            Payload::PubkeyHash(_) => 25,
+            // OP_HASH160 <20-byte push> OP_EQUAL
+            Payload::ScriptHash(_) => 23,
+            // <version> <program push>
+            Payload::WitnessProgram { program: ref prog, .. } => 2 + prog.len(),
         }
+    }
 
-        // Base58
-        if s.len() > 50 {
-            return Err(Error::Base58(base58::Error::InvalidLength(s.len() * 11 / 15)));
+    /// Returns the weight, in weight units, of a transaction output paying to this address.
+    ///
+    /// This is the 8-byte value, the compact size length prefix and the script pubkey, all of
+    /// which are non-witness data and so count [`WITNESS_SCALE_FACTOR`] times.
+    pub fn txout_weight(&self) -> usize {
+        let script_len = self.script_pubkey_len();
+        (8 + VarInt(script_len as u64).len() + script_len) * WITNESS_SCALE_FACTOR
+    }
+
+    /// Returns the typical weight, in weight units, of a transaction input spending an output
+    /// paying to this address, the counterpart of [`Address::txout_weight`].
+    ///
+    /// This assumes a single-signature spend with a 72-byte ECDSA signature and a compressed
+    /// public key, or a 64-byte schnorr signature for a taproot key path spend, and excludes
+    /// the segwit marker and flag of the transaction. Returns `None` for P2SH and P2WSH, whose
+    /// spend size depends on the script, and for addresses of other types.
+    pub fn spend_input_weight(&self) -> Option<usize> {
+        // outpoint, script sig length prefix and sequence
+        const BASE_INPUT_SIZE: usize = 32 + 4 + 1 + 4;
+        // push of a DER signature with sighash flag, push of a compressed public key
+        const ECDSA_SIG_PUBKEY_SIZE: usize = 1 + 72 + 1 + 33;
+
+        match self.address_type()? {
+            AddressType::P2pkh => Some((BASE_INPUT_SIZE + ECDSA_SIG_PUBKEY_SIZE) * WITNESS_SCALE_FACTOR),
+            // witness item count followed by the items
+            AddressType::P2wpkh => Some(BASE_INPUT_SIZE * WITNESS_SCALE_FACTOR + 1 + ECDSA_SIG_PUBKEY_SIZE),
+            AddressType::P2tr => Some(BASE_INPUT_SIZE * WITNESS_SCALE_FACTOR + 1 + 1 + 64),
+            AddressType::P2sh | AddressType::P2wsh => None,
         }
-        let data = base58::from_check(s)?;
-        if data.len() != 21 {
-            return Err(Error::Base58(base58::Error::InvalidLength(data.len())));
+    }
+
+    /// Returns the dust threshold of an output paying to this address: outputs with a lower
+    /// value are dust and will not be relayed by Bitcoin Core.
+    ///
+    /// Follows Core's `GetDustThreshold`, charging `dust_relay_fee_per_kvb` (3000 sat/kvB by
+    /// default) for the size of the output plus the size of a typical input spending it, i.e.
+    /// 148 bytes for legacy outputs and 67 virtual bytes for segwit outputs.
+    pub fn dust_value(&self, dust_relay_fee_per_kvb: u64) -> Amount {
+        let input_size = match self.payload {
+            Payload::PubkeyHash(_) | Payload::ScriptHash(_) => 32 + 4 + 1 + 107 + 4,
+            Payload::WitnessProgram { .. } => 32 + 4 + 1 + 107 / WITNESS_SCALE_FACTOR + 4,
+        };
+        let size = self.txout_weight() / WITNESS_SCALE_FACTOR + input_size;
+        Amount::from_sat(size as u64 * dust_relay_fee_per_kvb / 1000)
+    }
+
+    /// Returns an [`AddressEncoding`] that displays this address with a custom bech32
+    /// human-readable part, e.g. for a custom signet that does not use `tb`.
+    ///
+    /// Base58 addresses keep the prefixes of [`Address::network`]. The HRP must be 1 to 83
+    /// lowercase ASCII letters or digits. Use [`AddressEncoding::decode`] with the same HRP
+    /// to parse the result back.
+    pub fn with_custom_hrp<'a>(&'a self, hrp: &'a str) -> Result<AddressEncoding<'a>, Error> {
+        let valid = !hrp.is_empty()
+            && hrp.len() <= 83
+            && hrp.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit());
+        if !valid {
+            return Err(Error::InvalidHrp(hrp.to_owned()));
         }
+        let mut encoding = self.encoding();
+        encoding.bech32_hrp = hrp;
+        Ok(encoding)
+    }
 
-        let (network, payload) = match data[0] {
-            PUBKEY_ADDRESS_PREFIX_MAIN => (
-                Network::Bitcoin,
-                Payload::PubkeyHash(PubkeyHash::from_slice(&data[1..]).unwrap()),
-            ),
-            SCRIPT_ADDRESS_PREFIX_MAIN => (
-                Network::Bitcoin,
-                Payload::ScriptHash(ScriptHash::from_slice(&data[1..]).unwrap()),
-            ),
-            PUBKEY_ADDRESS_PREFIX_TEST => (
-                Network::Testnet,
-                Payload::PubkeyHash(PubkeyHash::from_slice(&data[1..]).unwrap()),
-            ),
-            SCRIPT_ADDRESS_PREFIX_TEST => (
-                Network::Testnet,
-                Payload::ScriptHash(ScriptHash::from_slice(&data[1..]).unwrap()),
-            ),
-            x => return Err(Error::Base58(base58::Error::InvalidAddressVersion(x))),
+    /// Returns a hashable key identifying the script pubkey of this address.
+    ///
+    /// Equal [`ScriptPubkeyKey`]s imply equal script pubkeys. Only witness programs of
+    /// non-standard length allocate.
+    pub fn script_pubkey_key(&self) -> ScriptPubkeyKey {
+        match self.payload {
+            Payload::PubkeyHash(hash) => ScriptPubkeyKey::PubkeyHash(hash),
+            Payload::ScriptHash(hash) => ScriptPubkeyKey::ScriptHash(hash),
+            Payload::WitnessProgram { version, program: ref prog } => match prog.len() {
+                20 => {
+                    let mut arr = [0u8; 20];
+                    arr.copy_from_slice(prog);
+                    ScriptPubkeyKey::WitnessProgram20(version, arr)
+                }
+                32 => {
+                    let mut arr = [0u8; 32];
+                    arr.copy_from_slice(prog);
+                    ScriptPubkeyKey::WitnessProgram32(version, arr)
+                }
+                _ => ScriptPubkeyKey::OtherWitnessProgram(version, prog.clone()),
+            },
+        }
+    }
+
+    /// Returns the [`AddressEncoding`] parameters used to display this address on its network.
+    fn encoding(&self) -> AddressEncoding<'_> {
+        let p2pkh_prefix = match self.network {
+            Network::Bitcoin => PUBKEY_ADDRESS_PREFIX_MAIN,
+            Network::Testnet | Network::Testnet4 | Network::Signet | Network::Regtest => PUBKEY_ADDRESS_PREFIX_TEST,
+        };
+        let p2sh_prefix = match self.network {
+            Network::Bitcoin => SCRIPT_ADDRESS_PREFIX_MAIN,
+            Network::Testnet | Network::Testnet4 | Network::Signet | Network::Regtest => SCRIPT_ADDRESS_PREFIX_TEST,
         };
+        let bech32_hrp = match self.network {
+            Network::Bitcoin => "bc",
+            Network::Testnet | Network::Testnet4 | Network::Signet => "tb",
+            Network::Regtest => "bcrt",
+        };
+        AddressEncoding {
+            payload: &self.payload,
+            p2pkh_prefix,
+            p2sh_prefix,
+            bech32_hrp,
+        }
+    }
 
-        Ok(Address {
-            network: network,
-            payload: payload,
-        })
+    /// Writes the address string into `w`, without the intermediate allocation of `to_string`.
+    ///
+    /// This allows serializers to stream many addresses into one buffer.
+    pub fn encode_to<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        write!(w, "{}", self.encoding())
     }
-}
 
-impl fmt::Debug for Address {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.to_string())
+    /// Clears `buf` and writes the address string into it.
+    ///
+    /// Reusing the same buffer across many addresses avoids allocating a fresh `String` for
+    /// each of them as `to_string` does.
+    pub fn encode_into(&self, buf: &mut String) {
+        buf.clear();
+        self.encode_to(buf).expect("writing to a String doesn't fail");
+    }
+
+    /// Returns the address followed by its network in parentheses, e.g. `"tb1q... (testnet)"`.
+    ///
+    /// Mainnet addresses are returned without a suffix.
+    pub fn to_display_with_network(&self) -> String {
+        match self.network {
+            Network::Bitcoin => self.to_string(),
+            network => format!("{} ({})", self, network),
+        }
+    }
+
+    /// Creates a URI string *bitcoin:address* optimized to be encoded in QR codes.
+    ///
+    /// If the address is bech32, both the schema and the address become uppercase.
+    /// If the address is base58, the schema is lowercase and the address is left mixed case.
+    ///
+    /// Quoting BIP 173 "inside QR codes uppercase SHOULD be used, as those permit the use of
+    /// alphanumeric mode, which is 45% more compact than the normal byte mode."
+    pub fn to_qr_uri(&self) -> String {
+        let schema = match self.payload {
+            Payload::WitnessProgram { .. } => "BITCOIN",
+            _ => "bitcoin",
+        };
+        format!("{}:{:#}", schema, self)
+    }
+
+    /// Returns whether the URI produced by [`Address::to_qr_uri`] can be encoded in the QR
+    /// alphanumeric mode.
+    ///
+    /// This is the case for bech32 addresses, which are uppercased in the URI. Base58 addresses
+    /// contain lowercase characters and force the QR encoder into byte mode.
+    pub fn qr_uses_alphanumeric_mode(&self) -> bool {
+        match self.payload {
+            Payload::WitnessProgram { .. } => true,
+            Payload::PubkeyHash(_) | Payload::ScriptHash(_) => false,
+        }
+    }
+
+    /// Parsed addresses do not always have *one* network. The problem is that legacy testnet,
+    /// regtest and signet addresse use the same prefix instead of multiple different ones. When
+    /// parsing, such addresses are always assumed to be testnet addresses (the same is true for
+    /// bech32 signet addresses). So if one wants to check if an address belongs to a certain
+    /// network a simple comparison is not enough anymore. Instead this function can be used.
+    ///
+    /// ```rust
+    /// use bitcoin::{Address, Network};
+    ///
+    /// let address: Address = "2N83imGV3gPwBzKJQvWJ7cRUY2SpUyU6A5e".parse().unwrap();
+    /// assert!(address.is_valid_for_network(Network::Testnet));
+    /// assert!(address.is_valid_for_network(Network::Regtest));
+    /// assert!(address.is_valid_for_network(Network::Signet));
+    ///
+    /// assert_eq!(address.is_valid_for_network(Network::Bitcoin), false);
+    ///
+    /// let address: Address = "32iVBEu4dxkUQk9dJbZUiBiQdmypcEyJRf".parse().unwrap();
+    /// assert!(address.is_valid_for_network(Network::Bitcoin));
+    /// assert_eq!(address.is_valid_for_network(Network::Testnet), false);
+    /// ```
+    pub fn is_valid_for_network(&self, network: Network) -> bool {
+        let equivalence_classes = match self.payload {
+            Payload::PubkeyHash(_) | Payload::ScriptHash(_) => LEGACY_EQUIVALENCE_CLASSES,
+            Payload::WitnessProgram { .. } => SEGWIT_EQUIVALENCE_CLASSES,
+        };
+
+        equivalence_classes
+            .iter()
+            .any(|class| class.contains(&self.network) && class.contains(&network))
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use core::str::FromStr;
+/// Throwaway but valid addresses of each type, for use in tests.
+#[cfg(any(test, feature = "test-helpers"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-helpers")))]
+impl Address {
+    /// Returns a P2PKH address with an all-zero pubkey hash.
+    pub fn dummy_p2pkh(network: Network) -> Address {
+        Address { network, payload: Payload::PubkeyHash(PubkeyHash::from_inner([0; 20])) }
+    }
 
-    use hashes::hex::{FromHex, ToHex};
+    /// Returns a P2SH address with an all-zero script hash.
+    pub fn dummy_p2sh(network: Network) -> Address {
+        Address { network, payload: Payload::ScriptHash(ScriptHash::from_inner([0; 20])) }
+    }
 
-    use blockdata::script::Script;
-    use network::constants::Network::{Bitcoin, Testnet};
-    use util::ecdsa::PublicKey;
+    /// Returns a P2WPKH address with an all-zero witness program.
+    pub fn dummy_p2wpkh(network: Network) -> Address {
+        Address {
+            network,
+            payload: Payload::WitnessProgram { version: WitnessVersion::V0, program: vec![0; 20] },
+        }
+    }
 
-    use super::*;
+    /// Returns a P2WSH address with an all-zero witness program.
+    pub fn dummy_p2wsh(network: Network) -> Address {
+        Address {
+            network,
+            payload: Payload::WitnessProgram { version: WitnessVersion::V0, program: vec![0; 32] },
+        }
+    }
+
+    /// Returns a P2TR address whose output key is the x coordinate of the secp256k1 generator,
+    /// so that it is a valid x-only public key.
+    pub fn dummy_p2tr(network: Network) -> Address {
+        const GENERATOR_X: [u8; 32] = [
+            0x79, 0xbe, 0x66, 0x7e, 0xf9, 0xdc, 0xbb, 0xac, 0x55, 0xa0, 0x62, 0x95, 0xce, 0x87, 0x0b, 0x07,
+            0x02, 0x9b, 0xfc, 0xdb, 0x2d, 0xce, 0x28, 0xd9, 0x59, 0xf2, 0x81, 0x5b, 0x16, 0xf8, 0x17, 0x98,
+        ];
+        Address {
+            network,
+            payload: Payload::WitnessProgram { version: WitnessVersion::V1, program: GENERATOR_X.to_vec() },
+        }
+    }
+
+    /// Checks the string and script round-trip invariants of this address, for use in fuzzers
+    /// and property tests.
+    ///
+    /// Parsing the string form must give back the same payload on a network this address is
+    /// valid for; the network itself may differ since e.g. signet addresses parse as testnet.
+    /// Converting the script pubkey back with [`Address::from_script`] must give back this
+    /// exact address.
+    pub fn roundtrips_ok(&self) -> bool {
+        let string_ok = match Address::from_str(&self.to_string()) {
+            Ok(parsed) => parsed.payload == self.payload && parsed.is_valid_for_network(self.network),
+            Err(_) => false,
+        };
+        let script_ok = Address::from_script(&self.script_pubkey(), self.network).as_ref() == Some(self);
+        string_ok && script_ok
+    }
+}
+
+/// BIP-173 and BIP-350 test vectors of valid addresses, with the hex of their script pubkey.
+#[cfg(any(test, feature = "test-helpers"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-helpers")))]
+pub const BIP350_VALID: &[(&str, &str)] = &[
+    ("BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4", "0014751e76e8199196d454941c45d1b3a323f1433bd6"),
+    ("tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7", "00201863143c14c5166804bd19203356da136c985678cd4d27a1b8c6329604903262"),
+    ("bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kt5nd6y", "5128751e76e8199196d454941c45d1b3a323f1433bd6751e76e8199196d454941c45d1b3a323f1433bd6"),
+    ("BC1SW50QGDZ25J", "6002751e"),
+    ("bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs", "5210751e76e8199196d454941c45d1b3a323"),
+    ("tb1qqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesrxh6hy", "0020000000c4a5cad46221b2a187905e5266362b99d5e91c6ce24d165dab93e86433"),
+    ("tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c", "5120000000c4a5cad46221b2a187905e5266362b99d5e91c6ce24d165dab93e86433"),
+    ("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0", "512079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"),
+];
+
+/// BIP-173 and BIP-350 test vectors of invalid addresses, including the BIP-173 segwit v1+
+/// vectors made invalid by the switch to bech32m.
+#[cfg(any(test, feature = "test-helpers"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-helpers")))]
+pub const BIP350_INVALID: &[&str] = &[
+    // 1. BIP-350 test vectors
+    // Invalid human-readable part
+    "tc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq5zuyut",
+    // Invalid checksums (Bech32 instead of Bech32m):
+    "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqh2y7hd",
+    "tb1z0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqglt7rf",
+    "BC1S0XLXVLHEMJA6C4DQV22UAPCTQUPFHLXM9H8Z3K2E72Q4K9HCZ7VQ54WELL",
+    "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kemeawh",
+    "tb1q0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq24jc47",
+    // Invalid character in checksum
+    "bc1p38j9r5y49hruaue7wxjce0updqjuyyx0kh56v8s25huc6995vvpql3jow4",
+    // Invalid witness version
+    "BC130XLXVLHEMJA6C4DQV22UAPCTQUPFHLXM9H8Z3K2E72Q4K9HCZ7VQ7ZWS8R",
+    // Invalid program length (1 byte)
+    "bc1pw5dgrnzv",
+    // Invalid program length (41 bytes)
+    "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7v8n0nx0muaewav253zgeav",
+    // Invalid program length for witness version 0 (per BIP141)
+    "BC1QR508D6QEJXTDG4Y5R3ZARVARYV98GJ9P",
+    // Mixed case
+    "tb1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq47Zagq",
+    // zero padding of more than 4 bits
+    "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7v07qwwzcrf",
+    // Non-zero padding in 8-to-5 conversion
+    "tb1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vpggkg4j",
+    // Empty data section
+    "bc1gmk9yu",
+
+    // 2. BIP-173 test vectors
+    // Invalid human-readable part
+    "tc1qw508d6qejxtdg4y5r3zarvary0c5xw7kg3g4ty",
+    // Invalid checksum
+    "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5",
+    // Invalid witness version
+    "BC13W508D6QEJXTDG4Y5R3ZARVARY0C5XW7KN40WF2",
+    // Invalid program length
+    "bc1rw5uspcuh",
+    // Invalid program length
+    "bc10w508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kw5rljs90",
+    // Invalid program length for witness version 0 (per BIP141)
+    "BC1QR508D6QEJXTDG4Y5R3ZARVARYV98GJ9P",
+    // Mixed case
+    "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sL5k7",
+    // zero padding of more than 4 bits
+    "bc1zw508d6qejxtdg4y5r3zarvaryvqyzf3du",
+    // Non-zero padding in 8-to-5 conversion
+    "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3pjxtptv",
+    // Final test for empty data section is the same as above in BIP-350
+
+    // 3. BIP-173 valid test vectors obsolete by BIP-350
+    "bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7k7grplx",
+    "BC1SW50QA3JX3S",
+    "bc1zw508d6qejxtdg4y5r3zarvaryvg6kdaj",
+];
+
+/// The addresses implied by a bare multisig script, see [`Address::from_multisig_script`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MultisigAddresses {
+    /// Pay to script hash address with the multisig script as redeem script
+    pub p2sh: Address,
+    /// Pay to witness script hash address with the multisig script as witness script
+    pub p2wsh: Address,
+    /// P2SH-wrapped pay to witness script hash address
+    pub p2shwsh: Address,
+    /// Pay to public key hash addresses of the individual keys, in script order
+    pub p2pkh: Vec<Address>,
+}
+
+/// Iterator over the instructions of the script pubkey of an address, see
+/// [`Address::script_pubkey_instructions`].
+#[derive(Clone, Debug)]
+pub struct ScriptPubkeyInstructions<'a> {
+    payload: &'a Payload,
+    index: usize,
+}
+
+impl<'a> Iterator for ScriptPubkeyInstructions<'a> {
+    type Item = Instruction<'a>;
+
+    fn next(&mut self) -> Option<Instruction<'a>> {
+        use blockdata::opcodes::all::*;
+
+        let payload: &'a Payload = self.payload;
+        let instruction = match *payload {
+            Payload::PubkeyHash(ref hash) => match self.index {
+                0 => Instruction::Op(OP_DUP),
+                1 => Instruction::Op(OP_HASH160),
+                2 => Instruction::PushBytes(&hash[..]),
+                3 => Instruction::Op(OP_EQUALVERIFY),
+                4 => Instruction::Op(OP_CHECKSIG),
+                _ => return None,
+            },
+            Payload::ScriptHash(ref hash) => match self.index {
+                0 => Instruction::Op(OP_HASH160),
+                1 => Instruction::PushBytes(&hash[..]),
+                2 => Instruction::Op(OP_EQUAL),
+                _ => return None,
+            },
+            Payload::WitnessProgram { version, program: ref prog } => match self.index {
+                0 if version == WitnessVersion::V0 => Instruction::PushBytes(&[]),
+                0 => Instruction::Op(version.to_opcode()),
+                1 => Instruction::PushBytes(prog),
+                _ => return None,
+            },
+        };
+        self.index += 1;
+        Some(instruction)
+    }
+}
+
+/// A data push required in the script sig of a legacy spend, see [`ScriptSigTemplate`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ScriptSigPush {
+    /// An ECDSA signature with its sighash type
+    Signature,
+    /// The public key matching the pubkey hash
+    PublicKey,
+    /// Whatever pushes the redeem script requires, e.g. signatures for a multisig script
+    RedeemScriptInputs,
+    /// The serialized redeem script
+    RedeemScript,
+}
+
+/// The structure of the script sig spending an address, see [`Address::scriptsig_template`].
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ScriptSigTemplate {
+    /// The required pushes, in script order
+    pub pushes: Vec<ScriptSigPush>,
+}
+
+/// Creates addresses of different types for a fixed network.
+///
+/// Obtained from [`Address::builder`]; each method forwards to the [`Address`] constructor of
+/// the same name.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct AddressBuilder {
+    network: Network,
+}
+
+impl AddressBuilder {
+    /// Creates a pay to (compressed) public key hash address, see [`Address::p2pkh`].
+    pub fn p2pkh(&self, pk: &ecdsa::PublicKey) -> Address {
+        Address::p2pkh(pk, self.network)
+    }
+
+    /// Creates a pay to script hash address, see [`Address::p2sh`].
+    pub fn p2sh(&self, script: &script::Script) -> Result<Address, Error> {
+        Address::p2sh(script, self.network)
+    }
+
+    /// Creates a witness pay to public key address, see [`Address::p2wpkh`].
+    pub fn p2wpkh(&self, pk: &ecdsa::PublicKey) -> Result<Address, Error> {
+        Address::p2wpkh(pk, self.network)
+    }
+
+    /// Creates a P2SH-wrapped witness pay to public key address, see [`Address::p2shwpkh`].
+    pub fn p2shwpkh(&self, pk: &ecdsa::PublicKey) -> Result<Address, Error> {
+        Address::p2shwpkh(pk, self.network)
+    }
+
+    /// Creates a witness pay to script hash address, see [`Address::p2wsh`].
+    pub fn p2wsh(&self, script: &script::Script) -> Address {
+        Address::p2wsh(script, self.network)
+    }
+
+    /// Creates a P2SH-wrapped witness pay to script hash address, see [`Address::p2shwsh`].
+    pub fn p2shwsh(&self, script: &script::Script) -> Address {
+        Address::p2shwsh(script, self.network)
+    }
+
+    /// Creates a pay to taproot address, see [`Address::p2tr`].
+    pub fn p2tr(&self, taptweaked_key: schnorrsig::PublicKey) -> Address {
+        Address::p2tr(taptweaked_key, self.network)
+    }
+}
+
+/// Checks whether an output script is an `OP_RETURN` output, which is provably unspendable and
+/// has no address.
+pub fn is_op_return(script: &script::Script) -> bool {
+    script.is_op_return()
+}
+
+/// Returns the data committed to by an `OP_RETURN` output script.
+///
+/// Returns `None` if the script is not an `OP_RETURN` output or if `OP_RETURN` is not followed by
+/// at most a single data push. A bare `OP_RETURN` commits to empty data.
+pub fn op_return_data(script: &script::Script) -> Option<&[u8]> {
+    if !is_op_return(script) {
+        return None;
+    }
+    let mut instructions = script.instructions().skip(1);
+    match (instructions.next(), instructions.next()) {
+        (None, _) => Some(&[][..]),
+        (Some(Ok(Instruction::PushBytes(data))), None) => Some(data),
+        _ => None,
+    }
+}
+
+/// The maximum length of an address string accepted for decoding.
+///
+/// Valid addresses are at most 90 characters, the bech32 limit; longer strings are rejected
+/// up front so that arbitrarily long input does not reach the decoders.
+pub const MAX_ADDRESS_LEN: usize = 1000;
+
+/// The x-only public key suggested by BIP-341 as internal key for outputs whose key path must be
+/// unspendable: the point `H` obtained by hashing the secp256k1 generator, whose discrete
+/// logarithm is unknown.
+pub const NUMS_INTERNAL_KEY: [u8; 32] = [
+    0x50, 0x92, 0x9b, 0x74, 0xc1, 0xa0, 0x49, 0x54, 0xb7, 0x8b, 0x4b, 0x60, 0x35, 0xe9, 0x7a, 0x5e,
+    0x07, 0x8a, 0x5a, 0x0f, 0x28, 0xec, 0x96, 0xd5, 0x47, 0xbf, 0xee, 0x9a, 0xce, 0x80, 0x3a, 0xc0,
+];
+
+/// The number of trailing address characters revealed by [`Address::log_fingerprint`].
+pub const LOG_FINGERPRINT_SUFFIX_LEN: usize = 4;
+
+/// Groups of networks sharing the same base58 prefixes, hence the same legacy addresses.
+const LEGACY_EQUIVALENCE_CLASSES: &[&[Network]] = &[
+    &[Network::Bitcoin],
+    &[Network::Testnet, Network::Testnet4, Network::Regtest, Network::Signet],
+];
+
+/// Groups of networks sharing the same bech32 HRP, hence the same segwit addresses.
+const SEGWIT_EQUIVALENCE_CLASSES: &[&[Network]] = &[
+    &[Network::Bitcoin],
+    &[Network::Regtest],
+    &[Network::Testnet, Network::Testnet4, Network::Signet],
+];
+
+/// A utility struct to encode an address payload with the given parameters.
+/// This is a low-level utility struct. Consider using `Address` instead.
+pub struct AddressEncoding<'a> {
+    /// The address payload to encode.
+    pub payload: &'a Payload,
+    /// base58 version byte for p2pkh payloads (e.g. 0x00 for "1..." addresses).
+    pub p2pkh_prefix: u8,
+    /// base58 version byte for p2sh payloads (e.g. 0x05 for "3..." addresses).
+    pub p2sh_prefix: u8,
+    /// hrp used in bech32 addresss (e.g. "bc" for "bc1..." addresses).
+    pub bech32_hrp: &'a str,
+}
+
+// Alternate formatting `{:#}` is used to return uppercase version of bech32 addresses which should
+// be used in QR codes, see [Address::to_qr_uri]
+impl<'a> fmt::Display for AddressEncoding<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self.payload {
+            Payload::PubkeyHash(ref hash) => {
+                let mut prefixed = [0; 21];
+                prefixed[0] = self.p2pkh_prefix;
+                prefixed[1..].copy_from_slice(&hash[..]);
+                base58::check_encode_slice_to_fmt(fmt, &prefixed[..])
+            }
+            Payload::ScriptHash(ref hash) => {
+                let mut prefixed = [0; 21];
+                prefixed[0] = self.p2sh_prefix;
+                prefixed[1..].copy_from_slice(&hash[..]);
+                base58::check_encode_slice_to_fmt(fmt, &prefixed[..])
+            }
+            Payload::WitnessProgram {
+                version,
+                program: ref prog,
+            } => {
+                let mut upper_writer;
+                let writer = if fmt.alternate() {
+                    upper_writer = UpperWriter(fmt);
+                    &mut upper_writer as &mut dyn fmt::Write
+                } else {
+                    fmt as &mut dyn fmt::Write
+                };
+                let mut bech32_writer = bech32::Bech32Writer::new(self.bech32_hrp, version.bech32_variant(), writer)?;
+                bech32::WriteBase32::write_u5(&mut bech32_writer, version.into())?;
+                bech32::ToBase32::write_base32(&prog, &mut bech32_writer)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.encoding().fmt(fmt)
+    }
+}
+
+struct UpperWriter<W: fmt::Write>(W);
+
+impl<W: fmt::Write> fmt::Write for UpperWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            self.0.write_char(c.to_ascii_uppercase())?;
+        }
+        Ok(())
+    }
+}
+
+/// Extract the bech32 prefix.
+/// Returns the same slice when no prefix is found.
+///
+/// Any string containing a `1` has a prefix, including base58 strings and malformed input
+/// with several separators, so callers only take the bech32 branch when the prefix is exactly
+/// a known human-readable part.
+fn find_bech32_prefix(bech32: &str) -> &str {
+    // Split at the last occurrence of the separator character '1'.
+    match bech32.rfind('1') {
+        None => bech32,
+        Some(sep) => bech32.split_at(sep).0,
+    }
+}
+
+/// Tweaks a taproot internal key with the given script tree merkle root, as specified by
+/// BIP-341, returning the output key.
+fn tap_tweak<C: Verification>(
+    secp: &Secp256k1<C>,
+    internal_key: schnorrsig::PublicKey,
+    merkle_root: &[u8],
+) -> schnorrsig::PublicKey {
+    let mut tweak_engine = TapTweakHash::engine();
+    tweak_engine.input(&internal_key.serialize());
+    tweak_engine.input(merkle_root);
+    let tweak = TapTweakHash::from_engine(tweak_engine);
+
+    let mut output_key = internal_key;
+    output_key.tweak_add_assign(secp, &tweak[..]).expect("tap tweak is negligibly likely to overflow");
+    output_key
+}
+
+/// Returns the URI scheme `s` starts with, e.g. `bitcoincash` for a Bitcoin Cash cashaddr.
+///
+/// Neither base58 nor our bech32 addresses can contain a `:`, so any scheme means the string
+/// is not a plain Bitcoin address.
+fn find_uri_scheme(s: &str) -> Option<&str> {
+    let scheme = &s[..s.find(':')?];
+    let mut chars = scheme.chars();
+    let starts_with_letter = match chars.next() {
+        Some(c) => c.is_ascii_alphabetic(),
+        None => false,
+    };
+    if starts_with_letter && chars.all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.') {
+        Some(scheme)
+    } else {
+        None
+    }
+}
+
+/// Returns the network of a known bech32 human-readable part, ignoring case.
+///
+/// Note that `tb` is shared by testnet and signet and is reported as [`Network::Testnet`].
+pub fn parse_hrp(hrp: &str) -> Option<Network> {
+    if hrp.eq_ignore_ascii_case("bc") {
+        Some(Network::Bitcoin)
+    } else if hrp.eq_ignore_ascii_case("tb") {
+        Some(Network::Testnet)
+    } else if hrp.eq_ignore_ascii_case("bcrt") {
+        Some(Network::Regtest)
+    } else {
+        None
+    }
+}
+
+/// Returns the ticker of the coin on `network`, `BTC` on mainnet and `tBTC` on every test
+/// network.
+pub fn ticker(network: Network) -> &'static str {
+    match network {
+        Network::Bitcoin => "BTC",
+        Network::Testnet | Network::Testnet4 | Network::Signet | Network::Regtest => "tBTC",
+    }
+}
+
+/// The bech32 data characters, indexed by their 5-bit value.
+const BECH32_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// Returns the witness version of a bech32(m) address string without decoding it.
+///
+/// The version is the first data character after the last `1` separator, e.g. `q` for
+/// [`WitnessVersion::V0`] and `p` for [`WitnessVersion::V1`]. This is only a cheap pre-check:
+/// the checksum and witness program are not validated. Returns `None` if the string does not
+/// look like a bech32 string, which includes every base58 address since those are mixed case.
+pub fn peek_witness_version(s: &str) -> Option<WitnessVersion> {
+    let sep = s.rfind('1')?;
+    let (hrp, data) = (&s[..sep], &s[sep + 1..]);
+    if hrp.is_empty() || !hrp.bytes().all(|b| b.is_ascii_graphic()) {
+        return None;
+    }
+    if s.bytes().any(|b| b.is_ascii_lowercase()) && s.bytes().any(|b| b.is_ascii_uppercase()) {
+        return None;
+    }
+    if !data.bytes().all(|b| BECH32_CHARSET.contains(&b.to_ascii_lowercase())) {
+        return None;
+    }
+    let first = data.bytes().next()?.to_ascii_lowercase();
+    let value = BECH32_CHARSET.iter().position(|&c| c == first)? as u8;
+    let version = bech32::u5::try_from_u8(value).ok()?;
+    WitnessVersion::from_u5(version).ok()
+}
+
+/// Returns whether `s` decodes as bech32 with the checksum variant `version` requires.
+fn has_bech32_variant_of(s: &str, version: WitnessVersion) -> bool {
+    match bech32::decode(s) {
+        Ok((_, _, variant)) => variant == version.bech32_variant(),
+        Err(_) => false,
+    }
+}
+
+/// Checks the witness program length limits of BIP-141, which any encodable segwit payload
+/// must satisfy.
+fn check_witness_program(version: WitnessVersion, program: &[u8]) -> Result<(), Error> {
+    check_witness_program_len(version, program.len())
+}
+
+/// Checks the witness program length limits of BIP-141 given only the program length.
+fn check_witness_program_len(version: WitnessVersion, len: usize) -> Result<(), Error> {
+    if len < 2 {
+        return Err(Error::WitnessProgramTooShort(len));
+    }
+    if len > 40 {
+        return Err(Error::WitnessProgramTooLong(len));
+    }
+
+    // Specific segwit v0 check.
+    if version == WitnessVersion::V0 && (len != 20 && len != 32) {
+        return Err(Error::InvalidSegwitV0ProgramLength(len));
+    }
+    Ok(())
+}
+
+/// Decodes the payload of a bech32(m) encoded segwit address, validating the witness program.
+///
+/// The human-readable part is not checked here.
+fn decode_bech32_payload(s: &str) -> Result<Payload, Error> {
+    let (_, payload, variant) = bech32::decode(s)?;
+    if payload.is_empty() {
+        return Err(Error::EmptyBech32Payload);
+    }
+
+    // Get the script version and program (converted from 5-bit to 8-bit)
+    let (version, program): (WitnessVersion, Vec<u8>) = {
+        let (v, p5) = payload.split_at(1);
+        (WitnessVersion::from_u5(v[0])?, bech32::FromBase32::from_base32(p5)?)
+    };
+
+    check_witness_program(version, &program)?;
+
+    // Encoding check
+    let expected = version.bech32_variant();
+    if expected != variant {
+        return Err(Error::InvalidBech32Variant { expected, found: variant });
+    }
+
+    Ok(Payload::WitnessProgram {
+        version: version,
+        program: program,
+    })
+}
+
+/// Runs the checks of [`decode_bech32_payload`] without converting the witness program to
+/// bytes, see [`Address::validate`].
+fn validate_bech32_payload(s: &str) -> Result<(), Error> {
+    let (_, payload, variant) = bech32::decode(s)?;
+    if payload.is_empty() {
+        return Err(Error::EmptyBech32Payload);
+    }
+
+    let (v, p5) = payload.split_at(1);
+    let version = WitnessVersion::from_u5(v[0])?;
+
+    // Same padding rules as the 5 to 8 bit conversion of `FromBase32`: less than 5 bits left
+    // over, all of them zero.
+    let padding = p5.len() * 5 % 8;
+    if padding >= 5 {
+        return Err(bech32::Error::InvalidPadding.into());
+    }
+    if let Some(last) = p5.last() {
+        if last.to_u8() & ((1 << padding) - 1) != 0 {
+            return Err(bech32::Error::InvalidPadding.into());
+        }
+    }
+
+    check_witness_program_len(version, p5.len() * 5 / 8)?;
+
+    let expected = version.bech32_variant();
+    if expected != variant {
+        return Err(Error::InvalidBech32Variant { expected, found: variant });
+    }
+    Ok(())
+}
+
+/// Collects every error [`decode_bech32_payload`] could return for `s`, see
+/// [`Address::diagnose`].
+///
+/// The witness version and program are read from the data part without relying on the
+/// checksum, so that they are checked even when the checksum is wrong.
+fn diagnose_bech32_payload(s: &str, errors: &mut Vec<Error>) {
+    let variant = match bech32::decode(s) {
+        Ok((_, _, variant)) => Some(variant),
+        Err(e) => {
+            errors.push(e.into());
+            None
+        }
+    };
+
+    let data = match s.rfind('1') {
+        Some(sep) => &s.as_bytes()[sep + 1..],
+        None => return,
+    };
+    if data.len() < 6 {
+        return;
+    }
+    let mut payload = Vec::with_capacity(data.len() - 6);
+    for b in &data[..data.len() - 6] {
+        match BECH32_CHARSET.iter().position(|&c| c == b.to_ascii_lowercase()) {
+            Some(value) => payload.push(bech32::u5::try_from_u8(value as u8).expect("charset has 32 entries")),
+            // already reported by bech32::decode
+            None => return,
+        }
+    }
+    if payload.is_empty() {
+        errors.push(Error::EmptyBech32Payload);
+        return;
+    }
+
+    let version = WitnessVersion::from_u5(payload[0]);
+    let program: Result<Vec<u8>, _> = bech32::FromBase32::from_base32(&payload[1..]);
+    match (version, program) {
+        (Ok(version), Ok(program)) => {
+            if let Err(e) = check_witness_program(version, &program) {
+                errors.push(e);
+            }
+            let expected = version.bech32_variant();
+            match variant {
+                Some(found) if found != expected => {
+                    errors.push(Error::InvalidBech32Variant { expected, found });
+                }
+                _ => {}
+            }
+        }
+        (version, program) => {
+            if let Err(e) = version {
+                errors.push(e);
+            }
+            if let Err(e) = program {
+                errors.push(e.into());
+            }
+        }
+    }
+}
+
+/// Decodes a base58check encoded legacy address into its version byte followed by the 20-byte
+/// hash.
+fn decode_base58_data(s: &str) -> Result<Vec<u8>, Error> {
+    if s.len() > 50 {
+        return Err(Error::Base58(base58::Error::InvalidLength(s.len() * 11 / 15)));
+    }
+    let data = match base58::from_check(s) {
+        Ok(data) => data,
+        Err(e) => {
+            // Base58 needs lowercase letters in practice, a string without any was most
+            // likely uppercased somewhere along the way.
+            let has_upper = s.chars().any(|c| c.is_ascii_uppercase());
+            let has_lower = s.chars().any(|c| c.is_ascii_lowercase());
+            if has_upper && !has_lower {
+                return Err(Error::Base58UppercaseSuspected);
+            }
+            return Err(e.into());
+        }
+    };
+    if data.len() != 21 {
+        return Err(Error::Base58(base58::Error::InvalidLength(data.len())));
+    }
+    Ok(data)
+}
+
+impl<'a> AddressEncoding<'a> {
+    /// Decodes an address string into its payload, accepting only the given base58 version
+    /// bytes and bech32 human-readable part.
+    ///
+    /// This is the inverse of the [`fmt::Display`] implementation and mirrors
+    /// [`Address::from_str`], but lets tooling for other chains supply its own prefixes.
+    pub fn decode(
+        s: &str,
+        expected_p2pkh: u8,
+        expected_p2sh: u8,
+        expected_hrp: &str,
+    ) -> Result<Payload, Error> {
+        if s.len() > MAX_ADDRESS_LEN {
+            return Err(Error::AddressTooLong(s.len()));
+        }
+        if find_bech32_prefix(s).eq_ignore_ascii_case(expected_hrp) {
+            return decode_bech32_payload(s);
+        }
+
+        let data = decode_base58_data(s)?;
+        match data[0] {
+            x if x == expected_p2pkh => Ok(Payload::PubkeyHash(PubkeyHash::from_slice(&data[1..])?)),
+            x if x == expected_p2sh => Ok(Payload::ScriptHash(ScriptHash::from_slice(&data[1..])?)),
+            x => Err(Error::Base58(base58::Error::InvalidAddressVersion(x))),
+        }
+    }
+}
+
+impl FromStr for Address {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Address, Error> {
+        if s.len() > MAX_ADDRESS_LEN {
+            return Err(Error::AddressTooLong(s.len()));
+        }
+        if let Some(scheme) = find_uri_scheme(s) {
+            return Err(Error::UnsupportedAddressScheme(scheme.to_owned()));
+        }
+
+        // try bech32
+        if let Some(network) = parse_hrp(find_bech32_prefix(s)) {
+            return Ok(Address {
+                payload: decode_bech32_payload(s)?,
+                network: network,
+            });
+        }
+
+        // Base58
+        let data = decode_base58_data(s)?;
+        let (network, payload) = match data[0] {
+            PUBKEY_ADDRESS_PREFIX_MAIN => (
+                Network::Bitcoin,
+                Payload::PubkeyHash(PubkeyHash::from_slice(&data[1..])?),
+            ),
+            SCRIPT_ADDRESS_PREFIX_MAIN => (
+                Network::Bitcoin,
+                Payload::ScriptHash(ScriptHash::from_slice(&data[1..])?),
+            ),
+            PUBKEY_ADDRESS_PREFIX_TEST => (
+                Network::Testnet,
+                Payload::PubkeyHash(PubkeyHash::from_slice(&data[1..])?),
+            ),
+            SCRIPT_ADDRESS_PREFIX_TEST => (
+                Network::Testnet,
+                Payload::ScriptHash(ScriptHash::from_slice(&data[1..])?),
+            ),
+            x => return Err(Error::Base58(base58::Error::InvalidAddressVersion(x))),
+        };
+
+        Ok(Address {
+            network: network,
+            payload: payload,
+        })
+    }
+}
+
+/// A parsed address whose network has not been checked yet.
+///
+/// Returned by [`Address::from_str_unchecked`]; use [`UncheckedAddress::require_network`] to
+/// get an [`Address`] only if it is valid for the network in use, or
+/// [`UncheckedAddress::assume_checked`] where any network is acceptable.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct UncheckedAddress(Address);
+
+impl UncheckedAddress {
+    /// Checks whether the address is valid for `network`, see [`Address::is_valid_for_network`].
+    pub fn is_valid_for_network(&self, network: Network) -> bool {
+        self.0.is_valid_for_network(network)
+    }
+
+    /// Returns the address if it is valid for `network`.
+    ///
+    /// Errors with [`Error::WrongNetwork`] otherwise.
+    pub fn require_network(self, network: Network) -> Result<Address, Error> {
+        if self.0.is_valid_for_network(network) {
+            Ok(self.0)
+        } else {
+            Err(Error::WrongNetwork { required: network, found: self.0.network })
+        }
+    }
+
+    /// Returns the address without checking its network.
+    pub fn assume_checked(self) -> Address {
+        self.0
+    }
+}
+
+impl Address {
+    /// Parses an address without asserting anything about its network, which has to be done
+    /// explicitly on the returned [`UncheckedAddress`] before using it.
+    pub fn from_str_unchecked(s: &str) -> Result<UncheckedAddress, Error> {
+        Address::from_str(s).map(UncheckedAddress)
+    }
+}
+
+/// Returns a placeholder, never a real address: the mainnet P2PKH address with an all-zero
+/// pubkey hash, for which nobody knows a key.
+///
+/// This only exists so that types containing an [`Address`] can derive [`Default`]; the value
+/// must be overwritten before use.
+impl Default for Address {
+    fn default() -> Address {
+        Address {
+            network: Network::Bitcoin,
+            payload: Payload::PubkeyHash(PubkeyHash::from_inner([0; 20])),
+        }
+    }
+}
+
+/// Shows the structure of the address along with its encoded form, which alone is available
+/// through [`fmt::Display`].
+impl fmt::Debug for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Address")
+            .field("network", &self.network)
+            .field("payload", &self.payload)
+            .field("encoded", &self.to_string())
+            .finish()
+    }
+}
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub mod as_network {
+    //! Serde support for addresses which must be valid for a given network.
+    //!
+    //! The serde implementation of [`Address`] accepts an address of any network. The wrappers
+    //! and `with` modules in this module additionally reject, during deserialization, addresses
+    //! which are not valid for their network according to [`Address::is_valid_for_network`].
+    //!
+    //! ```rust,ignore
+    //! use serde::{Serialize, Deserialize};
+    //! use bitcoin::Address;
+    //!
+    //! #[derive(Serialize, Deserialize)]
+    //! pub struct Withdrawal {
+    //!     #[serde(with = "bitcoin::util::address::as_network::mainnet")]
+    //!     pub destination: Address,
+    //! }
+    //! ```
+
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    use network::constants::Network;
+    use super::Address;
+
+    fn deserialize_for_network<'de, D>(d: D, network: Network) -> Result<Address, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let address = Address::deserialize(d)?;
+        if address.is_valid_for_network(network) {
+            Ok(address)
+        } else {
+            Err(de::Error::custom(format_args!("address {} is not valid for {}", address, network)))
+        }
+    }
+
+    macro_rules! network_address {
+        ($name:ident, $module:ident, $network:expr, $doc:expr, $module_doc:expr) => {
+            #[doc = $doc]
+            #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+            pub struct $name(pub Address);
+
+            impl Serialize for $name {
+                fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                    self.0.serialize(s)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $name {
+                fn deserialize<D: Deserializer<'de>>(d: D) -> Result<$name, D::Error> {
+                    deserialize_for_network(d, $network).map($name)
+                }
+            }
+
+            #[doc = $module_doc]
+            pub mod $module {
+                // methods are implementation of a standardized serde-specific signature
+                #![allow(missing_docs)]
+
+                use serde::{Deserializer, Serialize, Serializer};
+
+                use network::constants::Network;
+                use super::super::Address;
+
+                pub fn serialize<S: Serializer>(a: &Address, s: S) -> Result<S::Ok, S::Error> {
+                    a.serialize(s)
+                }
+
+                pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Address, D::Error> {
+                    super::deserialize_for_network(d, $network)
+                }
+            }
+        };
+    }
+
+    network_address!(
+        MainnetAddress, mainnet, Network::Bitcoin,
+        "An [`Address`] which fails to deserialize unless it is valid for Bitcoin mainnet.",
+        "Use with `#[serde(with = \"address::as_network::mainnet\")]` on an [`Address`] field."
+    );
+    network_address!(
+        TestnetAddress, testnet, Network::Testnet,
+        "An [`Address`] which fails to deserialize unless it is valid for Bitcoin testnet.",
+        "Use with `#[serde(with = \"address::as_network::testnet\")]` on an [`Address`] field."
+    );
+}
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub mod full_serde {
+    //! Serde support for addresses preserving their exact network.
+    //!
+    //! The serde implementation of [`Address`] serializes only the address string, from which
+    //! e.g. signet addresses are parsed back as testnet. Use with
+    //! `#[serde(with = "address::full_serde")]` on an [`Address`] field to serialize it as
+    //! `{ address, network }` instead, so that the network is restored exactly.
+    //! Deserialization fails if the address is not valid for the network.
+
+    // methods are implementation of a standardized serde-specific signature
+    #![allow(missing_docs)]
+
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    use network::constants::Network;
+    use super::Address;
+
+    #[derive(Serialize)]
+    struct FullAddressRef<'a> {
+        address: &'a Address,
+        network: Network,
+    }
+
+    #[derive(Deserialize)]
+    struct FullAddress {
+        address: Address,
+        network: Network,
+    }
+
+    pub fn serialize<S: Serializer>(a: &Address, s: S) -> Result<S::Ok, S::Error> {
+        FullAddressRef { address: a, network: a.network }.serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Address, D::Error> {
+        let full = FullAddress::deserialize(d)?;
+        if !full.address.is_valid_for_network(full.network) {
+            return Err(de::Error::custom(format_args!(
+                "address {} is not valid for {}", full.address, full.network,
+            )));
+        }
+        Ok(Address { network: full.network, payload: full.address.payload })
+    }
+}
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl Address {
+    /// Deserializes the addresses of an RPC `scriptPubKey.addresses` style field, which may be
+    /// either a single address string or an array of them.
+    ///
+    /// Errors if an element is not an address string or if any address is not valid for
+    /// `network`.
+    pub fn from_rpc_field<'de, D: ::serde::Deserializer<'de>>(
+        deserializer: D,
+        network: Network,
+    ) -> Result<Vec<Address>, D::Error> {
+        use serde::de::Error as DeError;
+        use serde::Deserialize;
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum OneOrMany {
+            One(Address),
+            Many(Vec<Address>),
+        }
+
+        let addresses = match OneOrMany::deserialize(deserializer)? {
+            OneOrMany::One(address) => vec![address],
+            OneOrMany::Many(addresses) => addresses,
+        };
+        if let Some(address) = addresses.iter().find(|a| !a.is_valid_for_network(network)) {
+            return Err(D::Error::custom(format_args!("address {} is not valid for {}", address, network)));
+        }
+        Ok(addresses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::str::FromStr;
+
+    use hashes::hex::{FromHex, ToHex};
+
+    use blockdata::script::Script;
+    use network::constants::Network::{Bitcoin, Testnet};
+    use util::ecdsa::PublicKey;
+
+    use super::*;
 
     macro_rules! hex (($hex:expr) => (Vec::from_hex($hex).unwrap()));
     macro_rules! hex_key (($hex:expr) => (PublicKey::from_slice(&hex!($hex)).unwrap()));
@@ -795,131 +2681,1303 @@ mod tests {
     macro_rules! hex_pubkeyhash (($hex:expr) => (PubkeyHash::from_hex(&$hex).unwrap()));
     macro_rules! hex_scripthash (($hex:expr) => (ScriptHash::from_hex($hex).unwrap()));
 
-    fn roundtrips(addr: &Address) {
+    fn roundtrips(addr: &Address) {
+        assert_eq!(
+            Address::from_str(&addr.to_string()).unwrap(),
+            *addr,
+            "string round-trip failed for {}",
+            addr,
+        );
+        assert_eq!(
+            Address::from_script(&addr.script_pubkey(), addr.network).as_ref(),
+            Some(addr),
+            "script round-trip failed for {}",
+            addr,
+        );
+        //TODO: add serde roundtrip after no-strason PR
+    }
+
+    #[test]
+    fn test_p2pkh_address_58() {
+        let addr = Address {
+            network: Bitcoin,
+            payload: Payload::PubkeyHash(hex_pubkeyhash!("162c5ea71c0b23f5b9022ef047c4a86470a5b070")),
+        };
+
+        assert_eq!(
+            addr.script_pubkey(),
+            hex_script!("76a914162c5ea71c0b23f5b9022ef047c4a86470a5b07088ac")
+        );
+        assert_eq!(&addr.to_string(), "132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM");
+        assert_eq!(addr.address_type(), Some(AddressType::P2pkh));
+        roundtrips(&addr);
+    }
+
+    #[test]
+    fn test_p2pkh_from_key() {
+        let key = hex_key!("048d5141948c1702e8c95f438815794b87f706a8d4cd2bffad1dc1570971032c9b6042a0431ded2478b5c9cf2d81c124a5e57347a3c63ef0e7716cf54d613ba183");
+        let addr = Address::p2pkh(&key, Bitcoin);
+        assert_eq!(&addr.to_string(), "1QJVDzdqb1VpbDK7uDeyVXy9mR27CJiyhY");
+
+        let key = hex_key!(&"03df154ebfcf29d29cc10d5c2565018bce2d9edbab267c31d2caf44a63056cf99f");
+        let addr = Address::p2pkh(&key, Testnet);
+        assert_eq!(&addr.to_string(), "mqkhEMH6NCeYjFybv7pvFC22MFeaNT9AQC");
+        assert_eq!(addr.address_type(), Some(AddressType::P2pkh));
+        roundtrips(&addr);
+    }
+
+    #[test]
+    fn test_payload_hex() {
+        let addr = Address::from_str("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM").unwrap();
+        assert_eq!(addr.payload_hex(), "162c5ea71c0b23f5b9022ef047c4a86470a5b070");
+
+        let addr = Address::from_str("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4").unwrap();
+        assert_eq!(addr.payload_hex(), "751e76e8199196d454941c45d1b3a323f1433bd6");
+        assert_eq!(addr.payload.as_bytes().len(), 20);
+    }
+
+    #[test]
+    fn test_into_from_parts() {
+        for s in &["132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM", "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"] {
+            let addr = Address::from_str(s).unwrap();
+            let (payload, network) = addr.clone().into_parts();
+            assert_eq!(Address::from_parts(payload, network), Ok(addr));
+        }
+
+        let payload = Payload::WitnessProgram { version: WitnessVersion::V0, program: vec![0; 21] };
+        assert_eq!(Address::from_parts(payload, Bitcoin), Err(Error::InvalidSegwitV0ProgramLength(21)));
+        let payload = Payload::WitnessProgram { version: WitnessVersion::V1, program: vec![0; 41] };
+        assert_eq!(Address::from_parts(payload, Bitcoin), Err(Error::WitnessProgramTooLong(41)));
+    }
+
+    #[test]
+    fn test_network_and_p2p_magic() {
+        let addr = Address::from_str("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM").unwrap();
+        assert_eq!(addr.network(), Bitcoin);
+        assert_eq!(addr.p2p_magic(), 0xD9B4BEF9);
+
+        let addr = Address::from_str("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx").unwrap();
+        assert_eq!(addr.network(), Testnet);
+        assert_eq!(addr.p2p_magic(), Testnet.magic());
+
+        let addr = Address::dummy_p2wpkh(Network::Regtest);
+        assert_eq!(Network::from_magic(addr.p2p_magic()), Some(Network::Regtest));
+    }
+
+    #[test]
+    fn test_debug() {
+        let addr = Address::from_str("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM").unwrap();
+        let debug = format!("{:?}", addr);
+        assert!(debug.contains("Bitcoin"), "{}", debug);
+        assert!(debug.contains("PubkeyHash(162c5ea71c0b23f5b9022ef047c4a86470a5b070)"), "{}", debug);
+        assert!(debug.contains("\"132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM\""), "{}", debug);
+        assert_eq!(addr.to_string(), "132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM");
+    }
+
+    #[test]
+    fn test_typed_hash_accessors() {
+        let addr = Address::from_str("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM").unwrap();
+        assert_eq!(addr.pubkey_hash(), Some(hex_pubkeyhash!("162c5ea71c0b23f5b9022ef047c4a86470a5b070")));
+        assert_eq!(addr.script_hash(), None);
+
+        let addr = Address::from_str("33iFwdLuRpW1uK1RTRqsoi8rR4NpDzk66k").unwrap();
+        assert_eq!(addr.pubkey_hash(), None);
+        assert_eq!(addr.script_hash(), Some(hex_scripthash!("162c5ea71c0b23f5b9022ef047c4a86470a5b070")));
+
+        let addr = Address::from_str("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4").unwrap();
+        assert_eq!(addr.pubkey_hash(), None);
+        assert_eq!(addr.script_hash(), None);
+    }
+
+    #[test]
+    fn test_bloom_filter_elements() {
+        let addr = Address::from_str("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM").unwrap();
+        assert_eq!(addr.bloom_filter_elements(), vec![hex!("162c5ea71c0b23f5b9022ef047c4a86470a5b070")]);
+
+        // every element is a data push of the script pubkey
+        for addr in &["33iFwdLuRpW1uK1RTRqsoi8rR4NpDzk66k", "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"] {
+            let addr = Address::from_str(addr).unwrap();
+            let script = addr.script_pubkey();
+            let pushes: Vec<_> = script.instructions().filter_map(|i| match i.unwrap() {
+                Instruction::PushBytes(bytes) if !bytes.is_empty() => Some(bytes.to_vec()),
+                _ => None,
+            }).collect();
+            assert_eq!(addr.bloom_filter_elements(), pushes);
+        }
+    }
+
+    #[test]
+    fn test_payload_fingerprint() {
+        let addr = Address::from_str("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM").unwrap();
+        assert_eq!(addr.payload_fingerprint(), [0x16, 0x2c, 0x5e, 0xa7]);
+
+        // 2-byte witness program
+        let addr = Address::from_str("BC1SW50QGDZ25J").unwrap();
+        assert_eq!(addr.payload_fingerprint(), [0x75, 0x1e, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn test_p2sh_address_58() {
+        let addr = Address {
+            network: Bitcoin,
+            payload: Payload::ScriptHash(hex_scripthash!("162c5ea71c0b23f5b9022ef047c4a86470a5b070")),
+        };
+
+        assert_eq!(
+            addr.script_pubkey(),
+            hex_script!("a914162c5ea71c0b23f5b9022ef047c4a86470a5b07087")
+        );
+        assert_eq!(&addr.to_string(), "33iFwdLuRpW1uK1RTRqsoi8rR4NpDzk66k");
+        assert_eq!(addr.address_type(), Some(AddressType::P2sh));
+        roundtrips(&addr);
+    }
+
+    #[test]
+    fn test_p2sh_parse() {
+        let script = hex_script!("552103a765fc35b3f210b95223846b36ef62a4e53e34e2925270c2c7906b92c9f718eb2103c327511374246759ec8d0b89fa6c6b23b33e11f92c5bc155409d86de0c79180121038cae7406af1f12f4786d820a1466eec7bc5785a1b5e4a387eca6d797753ef6db2103252bfb9dcaab0cd00353f2ac328954d791270203d66c2be8b430f115f451b8a12103e79412d42372c55dd336f2eb6eb639ef9d74a22041ba79382c74da2338fe58ad21035049459a4ebc00e876a9eef02e72a3e70202d3d1f591fc0dd542f93f642021f82102016f682920d9723c61b27f562eb530c926c00106004798b6471e8c52c60ee02057ae");
+        let addr = Address::p2sh(&script, Testnet).unwrap();
+        assert_eq!(&addr.to_string(), "2N3zXjbwdTcPsJiy8sUK9FhWJhqQCxA8Jjr");
+        assert_eq!(addr.address_type(), Some(AddressType::P2sh));
+        roundtrips(&addr);
+    }
+
+    #[test]
+    fn test_p2sh_parse_for_large_script(){
+        let script = hex_script!("552103a765fc35b3f210b95223846b36ef62a4e53e34e2925270c2c7906b92c9f718eb2103c327511374246759ec8d0b89fa6c6b23b33e11f92c5bc155409d86de0c79180121038cae7406af1f12f4786d820a1466eec7bc5785a1b5e4a387eca6d797753ef6db2103252bfb9dcaab0cd00353f2ac328954d791270203d66c2be8b430f115f451b8a12103e79412d42372c55dd336f2eb6eb639ef9d74a22041ba79382c74da2338fe58ad21035049459a4ebc00e876a9eef02e72a3e70202d3d1f591fc0dd542f93f642021f82102016f682920d9723c61b27f562eb530c926c00106004798b6471e8c52c60ee02057ae12123122313123123ac1231231231231313123131231231231313212313213123123552103a765fc35b3f210b95223846b36ef62a4e53e34e2925270c2c7906b92c9f718eb2103c327511374246759ec8d0b89fa6c6b23b33e11f92c5bc155409d86de0c79180121038cae7406af1f12f4786d820a1466eec7bc5785a1b5e4a387eca6d797753ef6db2103252bfb9dcaab0cd00353f2ac328954d791270203d66c2be8b430f115f451b8a12103e79412d42372c55dd336f2eb6eb639ef9d74a22041ba79382c74da2338fe58ad21035049459a4ebc00e876a9eef02e72a3e70202d3d1f591fc0dd542f93f642021f82102016f682920d9723c61b27f562eb530c926c00106004798b6471e8c52c60ee02057ae12123122313123123ac1231231231231313123131231231231313212313213123123552103a765fc35b3f210b95223846b36ef62a4e53e34e2925270c2c7906b92c9f718eb2103c327511374246759ec8d0b89fa6c6b23b33e11f92c5bc155409d86de0c79180121038cae7406af1f12f4786d820a1466eec7bc5785a1b5e4a387eca6d797753ef6db2103252bfb9dcaab0cd00353f2ac328954d791270203d66c2be8b430f115f451b8a12103e79412d42372c55dd336f2eb6eb639ef9d74a22041ba79382c74da2338fe58ad21035049459a4ebc00e876a9eef02e72a3e70202d3d1f591fc0dd542f93f642021f82102016f682920d9723c61b27f562eb530c926c00106004798b6471e8c52c60ee02057ae12123122313123123ac1231231231231313123131231231231313212313213123123");
+        assert_eq!(Address::p2sh(&script, Testnet), Err(Error::ExcessiveScriptSize));
+    }
+
+    #[test]
+    fn test_p2wpkh() {
+        // stolen from Bitcoin transaction: b3c8c2b6cfc335abbcb2c7823a8453f55d64b2b5125a9a61e8737230cdb8ce20
+        let mut key = hex_key!("033bc8c83c52df5712229a2f72206d90192366c36428cb0c12b6af98324d97bfbc");
+        let addr = Address::p2wpkh(&key, Bitcoin).unwrap();
+        assert_eq!(&addr.to_string(), "bc1qvzvkjn4q3nszqxrv3nraga2r822xjty3ykvkuw");
+        assert_eq!(addr.address_type(), Some(AddressType::P2wpkh));
+        roundtrips(&addr);
+
+        // Test uncompressed pubkey
+        key.compressed = false;
+        assert_eq!(Address::p2wpkh(&key, Bitcoin), Err(Error::UncompressedPubkey));
+    }
+
+    #[test]
+    fn test_p2wsh() {
+        // stolen from Bitcoin transaction 5df912fda4becb1c29e928bec8d64d93e9ba8efa9b5b405bd683c86fd2c65667
+        let script = hex_script!("52210375e00eb72e29da82b89367947f29ef34afb75e8654f6ea368e0acdfd92976b7c2103a1b26313f430c4b15bb1fdce663207659d8cac749a0e53d70eff01874496feff2103c96d495bfdd5ba4145e3e046fee45e84a8a48ad05bd8dbb395c011a32cf9f88053ae");
+        let addr = Address::p2wsh(&script, Bitcoin);
+        assert_eq!(
+            &addr.to_string(),
+            "bc1qwqdg6squsna38e46795at95yu9atm8azzmyvckulcc7kytlcckxswvvzej"
+        );
+        assert_eq!(addr.address_type(), Some(AddressType::P2wsh));
+        roundtrips(&addr);
+    }
+
+    #[test]
+    fn test_p2shwpkh() {
+        // stolen from Bitcoin transaction: ad3fd9c6b52e752ba21425435ff3dd361d6ac271531fc1d2144843a9f550ad01
+        let mut key = hex_key!("026c468be64d22761c30cd2f12cbc7de255d592d7904b1bab07236897cc4c2e766");
+        let addr = Address::p2shwpkh(&key, Bitcoin).unwrap();
+        assert_eq!(&addr.to_string(), "3QBRmWNqqBGme9er7fMkGqtZtp4gjMFxhE");
+        assert_eq!(addr.address_type(), Some(AddressType::P2sh));
+        roundtrips(&addr);
+
+        // Test uncompressed pubkey
+        key.compressed = false;
+        assert_eq!(Address::p2wpkh(&key, Bitcoin), Err(Error::UncompressedPubkey));
+    }
+
+    #[test]
+    fn test_p2shwsh() {
+        // stolen from Bitcoin transaction f9ee2be4df05041d0e0a35d7caa3157495ca4f93b233234c9967b6901dacf7a9
+        let script = hex_script!("522103e5529d8eaa3d559903adb2e881eb06c86ac2574ffa503c45f4e942e2a693b33e2102e5f10fcdcdbab211e0af6a481f5532536ec61a5fdbf7183770cf8680fe729d8152ae");
+        let addr = Address::p2shwsh(&script, Bitcoin);
+        assert_eq!(&addr.to_string(), "36EqgNnsWW94SreZgBWc1ANC6wpFZwirHr");
+        assert_eq!(addr.address_type(), Some(AddressType::P2sh));
+        roundtrips(&addr);
+    }
+
+    #[test]
+    fn test_p2tr_from_output_key() {
+        let key = schnorrsig::PublicKey::from_slice(
+            &hex!("79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+        ).unwrap();
+        let payload = Payload::p2tr_from_output_key(key);
+        assert_eq!(payload, Address::p2tr(key, Bitcoin).payload);
+
+        let addr = Address { payload, network: Bitcoin };
+        assert_eq!(addr.to_string(), "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0");
+        assert_eq!(addr.address_type(), Some(AddressType::P2tr));
+        roundtrips(&addr);
+    }
+
+    #[test]
+    fn test_from_p2pk_script() {
+        use blockdata::constants::genesis_block;
+
+        // the genesis block coinbase pays to a bare public key
+        let script = &genesis_block(Bitcoin).txdata[0].output[0].script_pubkey;
+        assert!(script.is_p2pk());
+        let addr = Address::from_p2pk_script(script, Bitcoin).unwrap();
+        assert_eq!(&addr.to_string(), "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa");
+
+        // compressed key
+        let key = hex_key!("03df154ebfcf29d29cc10d5c2565018bce2d9edbab267c31d2caf44a63056cf99f");
+        let addr = Address::from_p2pk_script(&Script::new_p2pk(&key), Testnet).unwrap();
+        assert_eq!(addr, Address::p2pkh(&key, Testnet));
+
+        let p2pkh = Address::p2pkh(&key, Testnet).script_pubkey();
+        assert_eq!(Address::from_p2pk_script(&p2pkh, Testnet), Err(Error::UnrecognizedScript));
+    }
+
+    #[test]
+    fn test_op_return_data() {
+        let data = [0xab; 40];
+        let script = Script::new_op_return(&data);
+        assert!(is_op_return(&script));
+        assert_eq!(op_return_data(&script), Some(&data[..]));
+
+        let script = Script::from(vec![opcodes::all::OP_RETURN.into_u8()]);
+        assert_eq!(op_return_data(&script), Some(&[][..]));
+
+        let script = hex_script!("76a914162c5ea71c0b23f5b9022ef047c4a86470a5b07088ac");
+        assert!(!is_op_return(&script));
+        assert_eq!(op_return_data(&script), None);
+    }
+
+    #[test]
+    fn test_address_encoding_decode() {
+        let payload = AddressEncoding::decode(
+            "132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM",
+            PUBKEY_ADDRESS_PREFIX_MAIN, SCRIPT_ADDRESS_PREFIX_MAIN, "bc",
+        ).unwrap();
+        assert_eq!(payload, Payload::PubkeyHash(hex_pubkeyhash!("162c5ea71c0b23f5b9022ef047c4a86470a5b070")));
+
+        let payload = AddressEncoding::decode(
+            "bc1qvzvkjn4q3nszqxrv3nraga2r822xjty3ykvkuw",
+            PUBKEY_ADDRESS_PREFIX_MAIN, SCRIPT_ADDRESS_PREFIX_MAIN, "bc",
+        ).unwrap();
+        assert_eq!(payload, Address::from_str("bc1qvzvkjn4q3nszqxrv3nraga2r822xjty3ykvkuw").unwrap().payload);
+
+        // prefixes not matching the expected ones are rejected
+        assert_eq!(
+            AddressEncoding::decode(
+                "132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM",
+                PUBKEY_ADDRESS_PREFIX_TEST, SCRIPT_ADDRESS_PREFIX_TEST, "tb",
+            ),
+            Err(Error::Base58(base58::Error::InvalidAddressVersion(PUBKEY_ADDRESS_PREFIX_MAIN))),
+        );
+        assert!(AddressEncoding::decode(
+            "bc1qvzvkjn4q3nszqxrv3nraga2r822xjty3ykvkuw",
+            PUBKEY_ADDRESS_PREFIX_TEST, SCRIPT_ADDRESS_PREFIX_TEST, "tb",
+        ).is_err());
+
+        // decoding is the inverse of encoding
+        let encoding = AddressEncoding {
+            payload: &payload,
+            p2pkh_prefix: 0x30,
+            p2sh_prefix: 0x32,
+            bech32_hrp: "ltc",
+        };
+        assert_eq!(AddressEncoding::decode(&encoding.to_string(), 0x30, 0x32, "ltc"), Ok(payload.clone()));
+    }
+
+    #[test]
+    fn test_address_encoding_custom_base58_prefix() {
+        let key = hex_key!("033bc8c83c52df5712229a2f72206d90192366c36428cb0c12b6af98324d97bfbc");
+        let script = hex_script!("0014751e76e8199196d454941c45d1b3a323f1433bd6");
+        let addrs = [Address::p2pkh(&key, Bitcoin), Address::p2sh(&script, Bitcoin).unwrap()];
+        for addr in addrs.iter() {
+            let encoding = AddressEncoding {
+                payload: &addr.payload,
+                p2pkh_prefix: 0x1C,
+                p2sh_prefix: 0x1D,
+                bech32_hrp: "bc",
+            };
+            let s = encoding.to_string();
+            assert_ne!(s, addr.to_string());
+            assert_eq!(AddressEncoding::decode(&s, 0x1C, 0x1D, "bc"), Ok(addr.payload.clone()));
+            assert!(Address::from_str(&s).is_err());
+        }
+    }
+
+    #[test]
+    fn test_from_descriptor_fragment() {
+        let bare = Address::from_descriptor_fragment("1QJVDzdqb1VpbDK7uDeyVXy9mR27CJiyhY").unwrap();
+        let wrapped = Address::from_descriptor_fragment("addr(1QJVDzdqb1VpbDK7uDeyVXy9mR27CJiyhY)").unwrap();
+        assert_eq!(bare, wrapped);
+        assert_eq!(bare, Address::from_str("1QJVDzdqb1VpbDK7uDeyVXy9mR27CJiyhY").unwrap());
+
+        assert_eq!(
+            Address::from_descriptor_fragment("addr(1QJVDzdqb1VpbDK7uDeyVXy9mR27CJiyhY"),
+            Err(Error::MalformedDescriptorFragment)
+        );
+        assert_eq!(
+            Address::from_descriptor_fragment("addr(1QJVDzdqb1VpbDK7uDeyVXy9mR27CJiyhY)x"),
+            Err(Error::MalformedDescriptorFragment)
+        );
+        assert!(Address::from_descriptor_fragment("addr(1QJVDzdqb1VpbDK7uDeyVXy9mR27CJiyhY))").is_err());
+    }
+
+    #[test]
+    fn test_parse_hrp() {
+        assert_eq!(parse_hrp("bc"), Some(Network::Bitcoin));
+        assert_eq!(parse_hrp("BC"), Some(Network::Bitcoin));
+        assert_eq!(parse_hrp("tb"), Some(Network::Testnet));
+        assert_eq!(parse_hrp("TB"), Some(Network::Testnet));
+        assert_eq!(parse_hrp("bcrt"), Some(Network::Regtest));
+        assert_eq!(parse_hrp("BCRT"), Some(Network::Regtest));
+        assert_eq!(parse_hrp("tc"), None);
+        assert_eq!(parse_hrp("ltc"), None);
+        assert_eq!(parse_hrp(""), None);
+    }
+
+    #[test]
+    fn test_same_payload() {
+        let key = hex_key!("033bc8c83c52df5712229a2f72206d90192366c36428cb0c12b6af98324d97bfbc");
+        let mainnet = Address::p2pkh(&key, Bitcoin);
+        let testnet = Address::p2pkh(&key, Testnet);
+        assert_ne!(mainnet, testnet);
+        assert!(mainnet.same_payload(&testnet));
+
+        let native = Address::p2wpkh(&key, Bitcoin).unwrap();
+        let wrapped = Address::p2shwpkh(&key, Bitcoin).unwrap();
+        assert!(!native.same_payload(&wrapped));
+    }
+
+    #[test]
+    fn test_address_builder() {
+        let key = hex_key!("033bc8c83c52df5712229a2f72206d90192366c36428cb0c12b6af98324d97bfbc");
+        let builder = Address::builder(Testnet);
+        assert_eq!(builder.p2pkh(&key), Address::p2pkh(&key, Testnet));
+        assert_eq!(builder.p2wpkh(&key), Address::p2wpkh(&key, Testnet));
+        assert_eq!(builder.p2shwpkh(&key), Address::p2shwpkh(&key, Testnet));
+
+        let script = hex_script!("522103e5529d8eaa3d559903adb2e881eb06c86ac2574ffa503c45f4e942e2a693b33e2102e5f10fcdcdbab211e0af6a481f5532536ec61a5fdbf7183770cf8680fe729d8152ae");
+        assert_eq!(builder.p2sh(&script), Address::p2sh(&script, Testnet));
+        assert_eq!(builder.p2wsh(&script), Address::p2wsh(&script, Testnet));
+        assert_eq!(builder.p2shwsh(&script), Address::p2shwsh(&script, Testnet));
+    }
+
+    #[test]
+    fn test_is_wrapped_segwit_for_pubkey() {
+        let addr = Address::from_str("3EZQk4F8GURH5sqVMLTFisD17yNeKa7Dfs").unwrap();
+        let pubkey = hex_key!("0347ff3dacd07a1f43805ec6808e801505a6e18245178609972a68afbc2777ff2b");
+        assert!(addr.is_wrapped_segwit_for_pubkey(&pubkey));
+
+        let other = hex_key!("033bc8c83c52df5712229a2f72206d90192366c36428cb0c12b6af98324d97bfbc");
+        assert!(!addr.is_wrapped_segwit_for_pubkey(&other));
+
+        // the native segwit address of the same key is not wrapped
+        let native = Address::p2wpkh(&pubkey, Bitcoin).unwrap();
+        assert!(!native.is_wrapped_segwit_for_pubkey(&pubkey));
+    }
+
+    #[test]
+    fn test_script_pubkey_key() {
+        use std::collections::HashMap;
+
+        let hash = hex_pubkeyhash!("162c5ea71c0b23f5b9022ef047c4a86470a5b070");
+        let main = Address { network: Bitcoin, payload: Payload::PubkeyHash(hash) };
+        let test = Address { network: Testnet, payload: Payload::PubkeyHash(hash) };
+        assert_eq!(main.script_pubkey_key(), test.script_pubkey_key());
+
+        let p2sh = Address { network: Bitcoin, payload: Payload::ScriptHash(hex_scripthash!("162c5ea71c0b23f5b9022ef047c4a86470a5b070")) };
+        assert_ne!(main.script_pubkey_key(), p2sh.script_pubkey_key());
+
+        let mut groups: HashMap<ScriptPubkeyKey, Vec<usize>> = HashMap::new();
+        let addrs = [main, p2sh, test];
+        for (i, addr) in addrs.iter().enumerate() {
+            groups.entry(addr.script_pubkey_key()).or_default().push(i);
+        }
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[&addrs[0].script_pubkey_key()], vec![0, 2]);
+
+        for addr in &[
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            "bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3",
+            "bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kt5nd6y",
+        ] {
+            let a = Address::from_str(addr).unwrap();
+            let b = Address::from_str(addr).unwrap();
+            assert_eq!(a.script_pubkey_key(), b.script_pubkey_key());
+        }
+    }
+
+    #[test]
+    fn test_with_custom_hrp() {
+        let addr = Address::from_str("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx").unwrap();
+        let encoded = addr.with_custom_hrp("tbs").unwrap().to_string();
+        assert!(encoded.starts_with("tbs1q"));
+
+        let payload = AddressEncoding::decode(
+            &encoded, PUBKEY_ADDRESS_PREFIX_TEST, SCRIPT_ADDRESS_PREFIX_TEST, "tbs",
+        ).unwrap();
+        assert_eq!(payload, addr.payload);
+
+        // base58 addresses are unaffected
+        let p2pkh = Address::from_str("mqkhEMH6NCeYjFybv7pvFC22MFeaNT9AQC").unwrap();
+        assert_eq!(p2pkh.with_custom_hrp("tbs").unwrap().to_string(), p2pkh.to_string());
+
+        for hrp in &["", "TBS", "t-s"] {
+            assert_eq!(addr.with_custom_hrp(hrp).err(), Some(Error::InvalidHrp(hrp.to_string())));
+        }
+    }
+
+    #[test]
+    fn test_custom_hrp_validation() {
+        let addr = Address::from_str("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx").unwrap();
+
+        let mainnet = Address { network: Bitcoin, payload: addr.payload.clone() };
+        assert_eq!(addr.with_custom_hrp("bc").unwrap().to_string(), mainnet.to_string());
+
+        let longest = "a".repeat(83);
+        assert!(addr.with_custom_hrp(&longest).is_ok());
+        let too_long = "a".repeat(84);
+        assert_eq!(addr.with_custom_hrp(&too_long).err(), Some(Error::InvalidHrp(too_long.clone())));
+
+        assert_eq!(addr.with_custom_hrp("").err(), Some(Error::InvalidHrp(String::new())));
+        assert_eq!(addr.with_custom_hrp("t\u{e9}").err(), Some(Error::InvalidHrp("t\u{e9}".to_owned())));
+    }
+
+    #[test]
+    fn test_p2sh_from_hash() {
+        let script = hex_script!("552103a765fc35b3f210b95223846b36ef62a4e53e34e2925270c2c7906b92c9f718eb2103c327511374246759ec8d0b89fa6c6b23b33e11f92c5bc155409d86de0c79180121038cae7406af1f12f4786d820a1466eec7bc5785a1b5e4a387eca6d797753ef6db2103252bfb9dcaab0cd00353f2ac328954d791270203d66c2be8b430f115f451b8a12103e79412d42372c55dd336f2eb6eb639ef9d74a22041ba79382c74da2338fe58ad21035049459a4ebc00e876a9eef02e72a3e70202d3d1f591fc0dd542f93f642021f82102016f682920d9723c61b27f562eb530c926c00106004798b6471e8c52c60ee02057ae");
+        let from_script = Address::p2sh(&script, Testnet).unwrap();
+        let from_hash = Address::p2sh_from_hash(ScriptHash::hash(&script[..]), Testnet);
+        assert_eq!(from_hash, from_script);
+        assert_eq!(from_hash.to_string(), "2N3zXjbwdTcPsJiy8sUK9FhWJhqQCxA8Jjr");
+    }
+
+    #[test]
+    fn test_from_trimmed_str() {
+        let expected = Address::from_str("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4").unwrap();
+        assert_eq!(
+            Address::from_trimmed_str("\u{FEFF}  bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4\r\n"),
+            Ok(expected)
+        );
+        assert_eq!(
+            Address::from_trimmed_str("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM\n"),
+            Address::from_str("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM")
+        );
+        assert!(Address::from_str("\u{FEFF}bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4").is_err());
+        // the BOM is only removed from the start
+        assert!(Address::from_trimmed_str("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4\u{FEFF}").is_err());
+    }
+
+    #[test]
+    fn test_sort_key() {
+        let mut addrs = [
+            Address::dummy_p2wpkh(Testnet),
+            Address::dummy_p2sh(Bitcoin),
+            Address::from_str("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4").unwrap(),
+            Address::dummy_p2pkh(Testnet),
+            Address::dummy_p2wpkh(Bitcoin),
+            Address::dummy_p2pkh(Bitcoin),
+        ];
+        addrs.sort_by_key(Address::sort_key);
+
+        let order: Vec<_> = addrs.iter().map(|a| (a.network, a.address_type().unwrap())).collect();
+        assert_eq!(order, vec![
+            (Bitcoin, AddressType::P2pkh),
+            (Bitcoin, AddressType::P2sh),
+            (Bitcoin, AddressType::P2wpkh),
+            (Bitcoin, AddressType::P2wpkh),
+            (Testnet, AddressType::P2pkh),
+            (Testnet, AddressType::P2wpkh),
+        ]);
+        // same network and type are ordered by payload bytes
+        assert_eq!(addrs[2], Address::dummy_p2wpkh(Bitcoin));
+    }
+
+    #[test]
+    fn test_is_nonstandard_taproot() {
+        let addr = Address {
+            network: Bitcoin,
+            payload: Payload::WitnessProgram { version: WitnessVersion::V1, program: vec![0x51; 33] },
+        };
+        assert!(addr.is_nonstandard_taproot());
+        assert_eq!(addr.address_type(), None);
+        roundtrips(&addr);
+
+        let addr = Address::from_str("bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kt5nd6y").unwrap();
+        assert!(addr.is_nonstandard_taproot());
+
+        let addr = Address::from_str("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0").unwrap();
+        assert!(!addr.is_nonstandard_taproot());
+        assert_eq!(addr.address_type(), Some(AddressType::P2tr));
+
+        assert!(!Address::dummy_p2wsh(Bitcoin).is_nonstandard_taproot());
+        assert!(!Address::dummy_p2pkh(Bitcoin).is_nonstandard_taproot());
+    }
+
+    #[test]
+    fn test_is_spendable_today() {
+        for addr in &[
+            "132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM",
+            "33iFwdLuRpW1uK1RTRqsoi8rR4NpDzk66k",
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            "bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3",
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+        ] {
+            assert!(Address::from_str(addr).unwrap().is_spendable_today(), "{}", addr);
+        }
+
+        let v5 = Address {
+            network: Bitcoin,
+            payload: Payload::WitnessProgram { version: WitnessVersion::V5, program: vec![0; 32] },
+        };
+        assert!(!v5.is_spendable_today());
+        // v1 program of the wrong length
+        let addr = Address::from_str("bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kt5nd6y").unwrap();
+        assert!(!addr.is_spendable_today());
+        // pay-to-anchor is anyone-can-spend
+        let anchor = Address::from_witness_program(WitnessVersion::V1, vec![0x4e, 0x73], Bitcoin).unwrap();
+        assert!(!anchor.is_spendable_today());
+    }
+
+    #[test]
+    fn test_parse_many() {
+        let input = "132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM\n\
+                     \n\
+                     \t bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 \n\
+                     not an address\n\
+                     132F25rTsvBdp9JzLLBHP5mvGY66i1xdi0";
+        let results = Address::parse_many(input.lines());
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0], Ok(Address::from_str("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM").unwrap()));
+        assert_eq!(results[1], Ok(Address::from_str("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4").unwrap()));
+        let failed: Vec<usize> = results.iter().filter_map(|r| r.as_ref().err().map(|&(i, _)| i)).collect();
+        assert_eq!(failed, vec![3, 4]);
+    }
+
+    #[test]
+    fn test_script_pubkey_bytes() {
+        for addr in &[
+            "132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM",
+            "33iFwdLuRpW1uK1RTRqsoi8rR4NpDzk66k",
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            "bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3",
+            "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr",
+            "bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kt5nd6y",
+            "BC1SW50QGDZ25J",
+        ] {
+            let addr = Address::from_str(addr).unwrap();
+            assert_eq!(addr.script_pubkey_bytes(), addr.script_pubkey().as_bytes().to_vec(), "{}", addr);
+        }
+    }
+
+    #[test]
+    fn test_matches_script_pubkey_bytes() {
+        for addr in &[
+            "132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM",
+            "33iFwdLuRpW1uK1RTRqsoi8rR4NpDzk66k",
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            "bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3",
+            "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr",
+            "BC1SW50QGDZ25J",
+        ] {
+            let addr = Address::from_str(addr).unwrap();
+            let spk = addr.script_pubkey().into_bytes();
+            assert!(addr.matches_script_pubkey_bytes(&spk), "{}", addr);
+
+            // right template, wrong hash or program; byte 3 is within the pushed data for
+            // every template
+            let mut near_miss = spk.clone();
+            near_miss[3] ^= 1;
+            assert!(!addr.matches_script_pubkey_bytes(&near_miss), "{}", addr);
+
+            assert!(!addr.matches_script_pubkey_bytes(&spk[..spk.len() - 1]));
+            assert!(!addr.matches_script_pubkey_bytes(&[]));
+        }
+
+        // same program under another witness version
+        let v0 = Address::from_str("bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3").unwrap();
+        let mut spk = v0.script_pubkey().into_bytes();
+        spk[0] = opcodes::all::OP_PUSHNUM_1.into_u8();
+        assert!(!v0.matches_script_pubkey_bytes(&spk));
+    }
+
+    #[test]
+    fn test_script_pubkey_instructions() {
+        for addr in &[
+            "132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM",
+            "33iFwdLuRpW1uK1RTRqsoi8rR4NpDzk66k",
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            "bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3",
+            "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr",
+            "bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs",
+            "BC1SW50QGDZ25J",
+        ] {
+            let addr = Address::from_str(addr).unwrap();
+            let script = addr.script_pubkey();
+            let parsed: Vec<_> = script.instructions().map(Result::unwrap).collect();
+            let built: Vec<_> = addr.script_pubkey_instructions().collect();
+            assert_eq!(built, parsed, "{}", addr);
+        }
+    }
+
+    #[test]
+    fn test_address_type_from_str() {
+        for address_type in AddressType::all().iter() {
+            assert_eq!(AddressType::from_str(&address_type.to_string()), Ok(*address_type));
+        }
+        let err = AddressType::from_str("p2pk").unwrap_err();
+        assert_eq!(err, Error::UnknownAddressType("p2pk".to_owned()));
+        assert_eq!(
+            err.to_string(),
+            "unknown address type 'p2pk', expected one of p2pkh, p2sh, p2wpkh, p2wsh, p2tr"
+        );
+    }
+
+    #[test]
+    fn test_taproot_output_key() {
+        let addr = Address::from_str("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0").unwrap();
+        let key = addr.taproot_output_key().unwrap();
+        assert_eq!(key.serialize().to_hex(), "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798");
+        assert_eq!(Address::p2tr(key, Bitcoin), addr);
+
+        let addr = Address::from_str("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4").unwrap();
+        assert_eq!(addr.taproot_output_key(), None);
+
+        // v1 program of the wrong length
+        let addr = Address::from_str("bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kt5nd6y").unwrap();
+        assert_eq!(addr.taproot_output_key(), None);
+    }
+
+    #[test]
+    fn test_dummy_addresses() {
+        for &network in &[Bitcoin, Testnet, Network::Testnet4, Network::Signet, Network::Regtest] {
+            let dummies = [
+                (Address::dummy_p2pkh(network), AddressType::P2pkh),
+                (Address::dummy_p2sh(network), AddressType::P2sh),
+                (Address::dummy_p2wpkh(network), AddressType::P2wpkh),
+                (Address::dummy_p2wsh(network), AddressType::P2wsh),
+                (Address::dummy_p2tr(network), AddressType::P2tr),
+            ];
+            for &(ref addr, address_type) in dummies.iter() {
+                assert_eq!(addr.address_type(), Some(address_type));
+                let parsed = Address::from_str(&addr.to_string()).unwrap();
+                assert!(parsed.is_valid_for_network(network));
+                assert_eq!(parsed.payload, addr.payload);
+            }
+        }
+        assert!(Address::dummy_p2tr(Bitcoin).taproot_output_key().is_some());
+    }
+
+    #[test]
+    fn test_roundtrips_ok() {
+        for &network in &[Bitcoin, Testnet, Network::Testnet4, Network::Signet, Network::Regtest] {
+            assert!(Address::dummy_p2pkh(network).roundtrips_ok());
+            assert!(Address::dummy_p2sh(network).roundtrips_ok());
+            assert!(Address::dummy_p2wpkh(network).roundtrips_ok());
+            assert!(Address::dummy_p2wsh(network).roundtrips_ok());
+            assert!(Address::dummy_p2tr(network).roundtrips_ok());
+        }
+        assert!(Address::from_str("BC1SW50QGDZ25J").unwrap().roundtrips_ok());
+
+        // a witness program that can't be encoded fails the string round trip
+        let invalid = Address {
+            network: Bitcoin,
+            payload: Payload::WitnessProgram { version: WitnessVersion::V0, program: vec![0; 21] },
+        };
+        assert!(!invalid.roundtrips_ok());
+    }
+
+    #[test]
+    fn test_regtest_segwit_roundtrip() {
+        for addr in &[
+            Address::dummy_p2wpkh(Network::Regtest),
+            Address::dummy_p2wsh(Network::Regtest),
+            Address::dummy_p2tr(Network::Regtest),
+        ] {
+            let s = addr.to_string();
+            assert!(s.starts_with("bcrt1"), "{}", s);
+            assert_eq!(Address::from_str(&s).unwrap(), *addr);
+            assert_eq!(Address::from_str(&s.to_uppercase()).unwrap(), *addr);
+            roundtrips(addr);
+
+            // Regtest segwit addresses don't share their HRP with testnet or signet
+            assert!(!addr.is_valid_for_network(Testnet));
+            assert!(!addr.is_valid_for_network(Network::Signet));
+        }
+    }
+
+    #[test]
+    fn test_error_clone_eq() {
+        fn assert_clone_eq<T: Clone + Eq>() {}
+        assert_clone_eq::<Error>();
+
+        let errors = [
+            Error::Base58(base58::Error::BadByte(b'0')),
+            Error::Bech32(bech32::Error::InvalidChecksum),
+            Error::EmptyBech32Payload,
+            Error::InvalidBech32Variant { expected: bech32::Variant::Bech32, found: bech32::Variant::Bech32m },
+            Error::InvalidWitnessVersion(17),
+            Error::UnparsableWitnessVersion("x".parse::<u8>().unwrap_err()),
+            Error::MalformedWitnessVersion,
+            Error::WitnessProgramTooShort(1),
+            Error::WitnessProgramTooLong(41),
+            Error::InvalidSegwitV0ProgramLength(21),
+            Error::InvalidSegwitV1ProgramLength(20),
+            Error::UncompressedPubkey,
+            Error::ExcessiveScriptSize,
+            Error::InvalidHashLength(19),
+            Error::UnrecognizedScript,
+            Error::MalformedDescriptorFragment,
+            Error::Base58UppercaseSuspected,
+            Error::InvalidHrp("TB".to_owned()),
+            Error::UnknownAddressType("p2pk".to_owned()),
+            Error::AddressTooLong(1001),
+            Error::WrongNetwork { required: Bitcoin, found: Testnet },
+            Error::UnsupportedWitnessVersion(WitnessVersion::V2),
+            Error::ScriptIsWitnessProgram,
+            Error::UnsupportedAddressScheme("bitcoincash".to_owned()),
+        ];
+        for (i, e) in errors.iter().enumerate() {
+            assert_eq!(e.clone(), *e);
+            for (j, other) in errors.iter().enumerate() {
+                assert_eq!(i == j, e == other);
+            }
+        }
+    }
+
+    #[test]
+    fn test_error_is_recoverable() {
+        assert!(Address::from_str("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5").unwrap_err().is_recoverable());
+        assert!(Address::from_str("132F25rTsvBdp9JzLLBHP5mvGY66i1xdi0").unwrap_err().is_recoverable());
+        assert!(Error::UnknownAddressType("p2pk".to_owned()).is_recoverable());
+
+        let uncompressed = hex_key!("04e96e22004e3db93530de27ccddfdf1463975d2138ac018fc3e7ba1a2e5e0aad8e424d0b55e2436eb1d0dcd5cb2b8bcc6d53412c22f358de57803a6a655fbbd04");
+        assert!(!Address::p2wpkh(&uncompressed, Bitcoin).unwrap_err().is_recoverable());
+        assert!(!Error::ExcessiveScriptSize.is_recoverable());
+    }
+
+    #[test]
+    fn test_uppercase_bech32_displays_lowercase() {
+        let addr = Address::from_str("BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4").unwrap();
+        assert_eq!(addr.to_string(), "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4");
+        assert_eq!(format!("{:#}", addr), "BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4");
+        assert_eq!(addr, Address::from_str("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4").unwrap());
+    }
+
+    #[test]
+    fn test_all_address_types() {
+        let key = hex_key!("033bc8c83c52df5712229a2f72206d90192366c36428cb0c12b6af98324d97bfbc");
+        let all = Address::all_address_types(&key, Bitcoin);
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0], (AddressType::P2pkh, Address::p2pkh(&key, Bitcoin)));
+        assert_eq!(all[1], (AddressType::P2wpkh, Address::p2wpkh(&key, Bitcoin).unwrap()));
+        assert_eq!(all[2], (AddressType::P2sh, Address::p2shwpkh(&key, Bitcoin).unwrap()));
+        for &(address_type, ref addr) in &all {
+            assert_eq!(addr.address_type(), Some(address_type));
+        }
+
+        let key = hex_key!("04e96e22004e3db93530de27ccddfdf1463975d2138ac018fc3e7ba1a2e5e0aad8e424d0b55e2436eb1d0dcd5cb2b8bcc6d53412c22f358de57803a6a655fbbd04");
+        let all = Address::all_address_types(&key, Bitcoin);
+        assert_eq!(all, vec![(AddressType::P2pkh, Address::p2pkh(&key, Bitcoin))]);
+    }
+
+    #[test]
+    fn test_from_multisig_script() {
+        let script = hex_script!("552103a765fc35b3f210b95223846b36ef62a4e53e34e2925270c2c7906b92c9f718eb2103c327511374246759ec8d0b89fa6c6b23b33e11f92c5bc155409d86de0c79180121038cae7406af1f12f4786d820a1466eec7bc5785a1b5e4a387eca6d797753ef6db2103252bfb9dcaab0cd00353f2ac328954d791270203d66c2be8b430f115f451b8a12103e79412d42372c55dd336f2eb6eb639ef9d74a22041ba79382c74da2338fe58ad21035049459a4ebc00e876a9eef02e72a3e70202d3d1f591fc0dd542f93f642021f82102016f682920d9723c61b27f562eb530c926c00106004798b6471e8c52c60ee02057ae");
+        let addrs = Address::from_multisig_script(&script, Testnet).unwrap();
+        assert_eq!(addrs.p2sh.to_string(), "2N3zXjbwdTcPsJiy8sUK9FhWJhqQCxA8Jjr");
+        assert_eq!(addrs.p2wsh, Address::p2wsh(&script, Testnet));
+        assert_eq!(addrs.p2shwsh, Address::p2shwsh(&script, Testnet));
+        assert_eq!(addrs.p2pkh.len(), 7);
+        assert_eq!(
+            addrs.p2pkh[0],
+            Address::p2pkh(&hex_key!("03a765fc35b3f210b95223846b36ef62a4e53e34e2925270c2c7906b92c9f718eb"), Testnet)
+        );
+
+        // not a multisig script
+        let p2pkh = Address::p2pkh(&hex_key!("03a765fc35b3f210b95223846b36ef62a4e53e34e2925270c2c7906b92c9f718eb"), Testnet);
+        assert_eq!(Address::from_multisig_script(&p2pkh.script_pubkey(), Testnet), Err(Error::UnrecognizedScript));
+        // key count doesn't match: 1-of-2 with a single key
+        let script = hex_script!("512103a765fc35b3f210b95223846b36ef62a4e53e34e2925270c2c7906b92c9f718eb52ae");
+        assert_eq!(Address::from_multisig_script(&script, Testnet), Err(Error::UnrecognizedScript));
+        // more signatures required than keys: 2-of-1
+        let script = hex_script!("522103a765fc35b3f210b95223846b36ef62a4e53e34e2925270c2c7906b92c9f718eb51ae");
+        assert_eq!(Address::from_multisig_script(&script, Testnet), Err(Error::UnrecognizedScript));
+        // valid 1-of-1
+        let script = hex_script!("512103a765fc35b3f210b95223846b36ef62a4e53e34e2925270c2c7906b92c9f718eb51ae");
+        assert!(Address::from_multisig_script(&script, Testnet).is_ok());
+    }
+
+    #[test]
+    fn test_p2sh_wpkh_redeem_script() {
+        let key = hex_key!("026c468be64d22761c30cd2f12cbc7de255d592d7904b1bab07236897cc4c2e766");
+        let addr = Address::p2shwpkh(&key, Bitcoin).unwrap();
+        let redeem_script = Address::p2sh_wpkh_redeem_script(&key).unwrap();
+        assert!(redeem_script.is_v0_p2wpkh());
+        assert_eq!(addr.script_hash(), Some(ScriptHash::hash(redeem_script.as_bytes())));
+
+        let key = hex_key!("04e96e22004e3db93530de27ccddfdf1463975d2138ac018fc3e7ba1a2e5e0aad8e424d0b55e2436eb1d0dcd5cb2b8bcc6d53412c22f358de57803a6a655fbbd04");
+        assert_eq!(Address::p2sh_wpkh_redeem_script(&key), Err(Error::UncompressedPubkey));
+    }
+
+    #[test]
+    fn test_p2sh_checked() {
+        let witness_program = hex_script!("0014751e76e8199196d454941c45d1b3a323f1433bd6");
+        assert_eq!(Address::p2sh_checked(&witness_program, Bitcoin), Err(Error::ScriptIsWitnessProgram));
+        assert!(Address::p2sh(&witness_program, Bitcoin).is_ok());
+
+        let multisig = hex_script!("52210375e00eb72e29da82b89367947f29ef34afb75e8654f6ea368e0acdfd92976b7c2103a1b26313f430c4b15bb1fdce663207659d8cac749a0e53d70eff01874496feff2103c96d495bfdd5ba4145e3e046fee45e84a8a48ad05bd8dbb395c011a32cf9f88053ae");
+        assert_eq!(Address::p2sh_checked(&multisig, Bitcoin), Address::p2sh(&multisig, Bitcoin));
+    }
+
+    #[test]
+    fn test_p2wpkh_from_hash() {
+        let key = hex_key!("033bc8c83c52df5712229a2f72206d90192366c36428cb0c12b6af98324d97bfbc");
+        let from_key = Address::p2wpkh(&key, Bitcoin).unwrap();
+        let from_hash = Address::p2wpkh_from_hash(key.wpubkey_hash().unwrap(), Bitcoin);
+        assert_eq!(from_hash, from_key);
+        assert_eq!(from_hash.address_type(), Some(AddressType::P2wpkh));
+        roundtrips(&from_hash);
+    }
+
+    #[test]
+    fn test_from_hash_bytes() {
+        let hash20 = [0x75; 20];
+        let hash32 = [0x75; 32];
+        for &address_type in AddressType::all().iter() {
+            let (good, bad) = match address_type {
+                AddressType::P2pkh | AddressType::P2sh | AddressType::P2wpkh => (&hash20[..], &hash32[..]),
+                AddressType::P2wsh | AddressType::P2tr => (&hash32[..], &hash20[..]),
+            };
+            let addr = Address::from_hash_bytes(good, address_type, Bitcoin).unwrap();
+            assert_eq!(addr.address_type(), Some(address_type));
+            assert_eq!(addr.payload.as_bytes(), good);
+            roundtrips(&addr);
+            assert_eq!(
+                Address::from_hash_bytes(bad, address_type, Bitcoin),
+                Err(Error::InvalidHashLength(bad.len()))
+            );
+        }
+        assert_eq!(
+            Address::from_hash_bytes(&[0; 19], AddressType::P2pkh, Bitcoin),
+            Err(Error::InvalidHashLength(19))
+        );
+    }
+
+    #[test]
+    fn test_p2wsh_from_hash() {
+        let script = hex_script!("52210375e00eb72e29da82b89367947f29ef34afb75e8654f6ea368e0acdfd92976b7c2103a1b26313f430c4b15bb1fdce663207659d8cac749a0e53d70eff01874496feff2103c96d495bfdd5ba4145e3e046fee45e84a8a48ad05bd8dbb395c011a32cf9f88053ae");
+        let from_script = Address::p2wsh(&script, Bitcoin);
+        let from_hash = Address::p2wsh_from_hash(WScriptHash::hash(&script[..]), Bitcoin);
+        assert_eq!(from_hash, from_script);
+        assert_eq!(from_hash.address_type(), Some(AddressType::P2wsh));
+        roundtrips(&from_hash);
+    }
+
+    #[test]
+    fn test_p2tr_single_leaf() {
+        // BIP-341 wallet test vector: scriptPubKey index 1
+        let secp = Secp256k1::verification_only();
+        let internal_key = schnorrsig::PublicKey::from_str(
+            "187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27"
+        ).unwrap();
+        let leaf = hex_script!("20d85a959b0290bf19bb89ed43c916be835475d013da4b362117393e25a48229b8ac");
+        let addr = Address::p2tr_single_leaf(&secp, internal_key, &leaf, Bitcoin);
+        assert_eq!(
+            addr.to_string(),
+            "bc1pz37fc4cn9ah8anwm4xqqhvxygjf9rjf2resrw8h8w4tmvcs0863sa2e586"
+        );
+        roundtrips(&addr);
+    }
+
+    #[test]
+    fn test_base58_version_byte() {
+        let p2pkh = Address::from_str("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM").unwrap();
+        assert_eq!(p2pkh.base58_version_byte(), Some(0x00));
+        let p2sh = Address::from_str("33iFwdLuRpW1uK1RTRqsoi8rR4NpDzk66k").unwrap();
+        assert_eq!(p2sh.base58_version_byte(), Some(0x05));
+        let testnet = Address::from_str("mqkhEMH6NCeYjFybv7pvFC22MFeaNT9AQC").unwrap();
+        assert_eq!(testnet.base58_version_byte(), Some(0x6f));
+        let segwit = Address::from_str("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx").unwrap();
+        assert_eq!(segwit.base58_version_byte(), None);
+    }
+
+    #[test]
+    fn test_peek_witness_version() {
+        assert_eq!(
+            peek_witness_version("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"),
+            Some(WitnessVersion::V0)
+        );
+        assert_eq!(
+            peek_witness_version("BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4"),
+            Some(WitnessVersion::V0)
+        );
+        assert_eq!(
+            peek_witness_version("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0"),
+            Some(WitnessVersion::V1)
+        );
+        assert_eq!(peek_witness_version("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM"), None);
+        assert_eq!(peek_witness_version("33iFwdLuRpW1uK1RTRqsoi8rR4NpDzk66k"), None);
+        assert_eq!(peek_witness_version("bc1"), None);
+        assert_eq!(peek_witness_version("1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"), None);
+        assert_eq!(peek_witness_version("bc1bw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"), None);
+    }
+
+    #[test]
+    fn test_scan_outputs() {
+        let p2pkh = Address::from_str("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM").unwrap();
+        let p2wpkh = Address::from_str("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4").unwrap();
+        let scripts = [
+            p2pkh.script_pubkey(),
+            hex_script!("6a0b68656c6c6f20776f726c64"),
+            p2wpkh.script_pubkey(),
+        ];
+
+        let found: Vec<_> = Address::scan_outputs(scripts.iter(), Bitcoin).collect();
+        assert_eq!(found, vec![(0, p2pkh), (2, p2wpkh)]);
+    }
+
+    #[test]
+    fn test_is_related_to_pubkey() {
+        let secp = ::secp256k1::Secp256k1::new();
+        let keys: Vec<_> = (1..6u8).map(|i| {
+            let sk = ::secp256k1::SecretKey::from_slice(&[i; 32]).unwrap();
+            PublicKey::new(::secp256k1::PublicKey::from_secret_key(&secp, &sk))
+        }).collect();
+
+        let p2wpkh = Address::p2wpkh(&keys[2], Bitcoin).unwrap();
+        assert_eq!(p2wpkh.is_related_to_any_pubkey(&secp, &keys), Some(2));
+        assert_eq!(p2wpkh.is_related_to_any_pubkey(&secp, &keys[3..]), None);
+
+        assert!(Address::p2pkh(&keys[0], Bitcoin).is_related_to_pubkey(&secp, &keys[0]));
+        assert!(Address::p2shwpkh(&keys[1], Bitcoin).unwrap().is_related_to_pubkey(&secp, &keys[1]));
+        let xonly = schnorrsig::PublicKey::from_slice(&keys[4].key.serialize()[1..]).unwrap();
+        let p2tr = Address::p2tr(xonly, Bitcoin);
+        assert_eq!(p2tr.is_related_to_any_pubkey(&secp, &keys), Some(4));
+        assert!(!Address::p2pkh(&keys[0], Bitcoin).is_related_to_pubkey(&secp, &keys[1]));
+
+        // BIP-86 first receiving address, m/86'/0'/0'/0/0
+        let internal_key = PublicKey::from_str(
+            "02cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115"
+        ).unwrap();
+        let bip86 = Address::from_str("bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr").unwrap();
+        let mut candidates = keys.clone();
+        candidates.insert(1, internal_key);
+        assert_eq!(bip86.is_related_to_any_pubkey(&secp, &candidates), Some(1));
+
+        // the parity and compression of the key don't matter for taproot
+        let uncompressed = PublicKey { compressed: false, key: internal_key.key };
+        assert!(bip86.is_related_to_pubkey(&secp, &uncompressed));
+        assert!(!bip86.is_related_to_pubkey(&secp, &keys[0]));
+    }
+
+    #[test]
+    fn test_from_witness_program() {
+        let addr = Address::from_witness_program(WitnessVersion::V0, vec![0x75; 20], Bitcoin).unwrap();
+        assert_eq!(addr.address_type(), Some(AddressType::P2wpkh));
+        let addr = Address::from_witness_program(WitnessVersion::V1, vec![0x75; 32], Bitcoin).unwrap();
+        assert_eq!(addr.address_type(), Some(AddressType::P2tr));
+        assert!(Address::from_witness_program(WitnessVersion::V1, vec![0x4e, 0x73], Bitcoin).is_ok());
+        assert!(Address::from_witness_program(WitnessVersion::V2, vec![0x75; 20], Bitcoin).is_ok());
+
+        assert_eq!(
+            Payload::new_witness_program(WitnessVersion::V0, vec![0; 21]),
+            Err(Error::InvalidSegwitV0ProgramLength(21))
+        );
+        assert_eq!(
+            Payload::new_witness_program(WitnessVersion::V1, vec![0; 20]),
+            Err(Error::InvalidSegwitV1ProgramLength(20))
+        );
+        assert_eq!(
+            Payload::new_witness_program(WitnessVersion::V2, vec![0; 1]),
+            Err(Error::WitnessProgramTooShort(1))
+        );
+        assert_eq!(
+            Payload::new_witness_program(WitnessVersion::V2, vec![0; 41]),
+            Err(Error::WitnessProgramTooLong(41))
+        );
+    }
+
+    #[test]
+    fn test_to_display_with_network() {
+        let testnet = Address::from_str("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx").unwrap();
+        assert_eq!(testnet.to_display_with_network(), "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx (testnet)");
+        let mainnet = Address::from_str("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM").unwrap();
+        assert_eq!(mainnet.to_display_with_network(), "132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM");
+    }
+
+    #[test]
+    fn test_log_fingerprint() {
+        let s = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+        let fingerprint = Address::from_str(s).unwrap().log_fingerprint();
+        assert_eq!(fingerprint, "p2wpkh:bc1q\u{2026}f3t4");
+        assert!(!fingerprint.contains(&s[4..s.len() - 4]));
+
+        let fingerprint = Address::from_str("33iFwdLuRpW1uK1RTRqsoi8rR4NpDzk66k").unwrap().log_fingerprint();
+        assert_eq!(fingerprint, "p2sh:3\u{2026}k66k");
+
+        let fingerprint = Address::from_str("bc1sw50qgdz25j").unwrap().log_fingerprint();
+        assert_eq!(fingerprint, "unknown:bc1s\u{2026}z25j");
+    }
+
+    #[test]
+    fn test_scriptsig_template() {
+        let p2pkh = Address::from_str("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM").unwrap();
         assert_eq!(
-            Address::from_str(&addr.to_string()).unwrap(),
-            *addr,
-            "string round-trip failed for {}",
-            addr,
+            p2pkh.scriptsig_template().unwrap().pushes,
+            vec![ScriptSigPush::Signature, ScriptSigPush::PublicKey]
         );
+        let p2sh = Address::from_str("33iFwdLuRpW1uK1RTRqsoi8rR4NpDzk66k").unwrap();
         assert_eq!(
-            Address::from_script(&addr.script_pubkey(), addr.network).as_ref(),
-            Some(addr),
-            "script round-trip failed for {}",
-            addr,
+            p2sh.scriptsig_template().unwrap().pushes,
+            vec![ScriptSigPush::RedeemScriptInputs, ScriptSigPush::RedeemScript]
         );
-        //TODO: add serde roundtrip after no-strason PR
+        let p2wpkh = Address::from_str("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4").unwrap();
+        assert_eq!(p2wpkh.scriptsig_template(), None);
     }
 
     #[test]
-    fn test_p2pkh_address_58() {
-        let addr = Address {
-            network: Bitcoin,
-            payload: Payload::PubkeyHash(hex_pubkeyhash!("162c5ea71c0b23f5b9022ef047c4a86470a5b070")),
-        };
+    fn test_unknown_bech32_prefix_falls_through_to_base58() {
+        for s in &["ab1cd1ef", "bc1cd1ef", "tb1x1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"] {
+            match Address::from_str(s) {
+                Err(Error::Base58(_)) => {}
+                other => panic!("expected a base58 error for {}, got {:?}", s, other),
+            }
+            let decoded = AddressEncoding::decode(
+                s, PUBKEY_ADDRESS_PREFIX_MAIN, SCRIPT_ADDRESS_PREFIX_MAIN, "bc",
+            );
+            match decoded {
+                Err(Error::Base58(_)) => {}
+                Err(e) => panic!("expected a base58 error for {}, got {:?}", s, e),
+                Ok(_) => panic!("{} should not decode", s),
+            }
+        }
+    }
+
+    #[test]
+    fn test_spend_input_weight() {
+        assert_eq!(Address::dummy_p2pkh(Bitcoin).spend_input_weight(), Some(592));
+        assert_eq!(Address::dummy_p2wpkh(Bitcoin).spend_input_weight(), Some(272));
+        assert_eq!(Address::dummy_p2tr(Bitcoin).spend_input_weight(), Some(230));
+        assert_eq!(Address::dummy_p2sh(Bitcoin).spend_input_weight(), None);
+        assert_eq!(Address::dummy_p2wsh(Bitcoin).spend_input_weight(), None);
+        assert_eq!(Address::from_str("bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs").unwrap().spend_input_weight(), None);
+    }
+
+    #[test]
+    fn test_dust_value() {
+        let p2pkh = Address::from_str("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM").unwrap();
+        assert_eq!(p2pkh.dust_value(3000), Amount::from_sat(546));
+        let p2sh = Address::from_str("33iFwdLuRpW1uK1RTRqsoi8rR4NpDzk66k").unwrap();
+        assert_eq!(p2sh.dust_value(3000), Amount::from_sat(540));
+        let p2wpkh = Address::from_str("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4").unwrap();
+        assert_eq!(p2wpkh.dust_value(3000), Amount::from_sat(294));
+        let p2wsh = Address::from_str("bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3").unwrap();
+        assert_eq!(p2wsh.dust_value(3000), Amount::from_sat(330));
+        assert_eq!(p2pkh.dust_value(0), Amount::from_sat(0));
+    }
 
+    #[test]
+    fn test_from_str_rejecting_future() {
+        let v2 = "bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs";
+        assert!(Address::from_str(v2).is_ok());
         assert_eq!(
-            addr.script_pubkey(),
-            hex_script!("76a914162c5ea71c0b23f5b9022ef047c4a86470a5b07088ac")
+            Address::from_str_rejecting_future(v2),
+            Err(Error::UnsupportedWitnessVersion(WitnessVersion::V2))
         );
-        assert_eq!(&addr.to_string(), "132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM");
-        assert_eq!(addr.address_type(), Some(AddressType::P2pkh));
-        roundtrips(&addr);
+        for s in &[
+            "132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM",
+            "33iFwdLuRpW1uK1RTRqsoi8rR4NpDzk66k",
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+        ] {
+            assert_eq!(Address::from_str_rejecting_future(s), Address::from_str(s));
+        }
     }
 
     #[test]
-    fn test_p2pkh_from_key() {
-        let key = hex_key!("048d5141948c1702e8c95f438815794b87f706a8d4cd2bffad1dc1570971032c9b6042a0431ded2478b5c9cf2d81c124a5e57347a3c63ef0e7716cf54d613ba183");
-        let addr = Address::p2pkh(&key, Bitcoin);
-        assert_eq!(&addr.to_string(), "1QJVDzdqb1VpbDK7uDeyVXy9mR27CJiyhY");
+    fn test_migrate_network() {
+        let testnet = [
+            Address::dummy_p2pkh(Testnet),
+            Address::dummy_p2sh(Testnet),
+            Address::dummy_p2wpkh(Testnet),
+            Address::dummy_p2tr(Testnet),
+        ];
+        let mainnet = Address::migrate_network(&testnet, Bitcoin);
+        assert_eq!(mainnet.len(), testnet.len());
+        for (from, to) in testnet.iter().zip(mainnet.iter()) {
+            assert_eq!(to.network, Bitcoin);
+            assert!(to.same_payload(from));
+            roundtrips(to);
+        }
+        assert!(mainnet[0].to_string().starts_with('1'));
+        assert!(mainnet[1].to_string().starts_with('3'));
+        assert!(mainnet[2].to_string().starts_with("bc1q"));
+        assert!(mainnet[3].to_string().starts_with("bc1p"));
 
-        let key = hex_key!(&"03df154ebfcf29d29cc10d5c2565018bce2d9edbab267c31d2caf44a63056cf99f");
-        let addr = Address::p2pkh(&key, Testnet);
-        assert_eq!(&addr.to_string(), "mqkhEMH6NCeYjFybv7pvFC22MFeaNT9AQC");
-        assert_eq!(addr.address_type(), Some(AddressType::P2pkh));
-        roundtrips(&addr);
+        let regtest = mainnet[2].with_network(Network::Regtest);
+        assert!(regtest.to_string().starts_with("bcrt1q"));
     }
 
     #[test]
-    fn test_p2sh_address_58() {
-        let addr = Address {
-            network: Bitcoin,
-            payload: Payload::ScriptHash(hex_scripthash!("162c5ea71c0b23f5b9022ef047c4a86470a5b070")),
-        };
+    fn test_is_segwit_legacy_taproot() {
+        let p2pkh = Address::dummy_p2pkh(Bitcoin);
+        assert!(p2pkh.is_legacy() && !p2pkh.is_segwit() && !p2pkh.is_taproot());
+        let p2sh = Address::dummy_p2sh(Bitcoin);
+        assert!(p2sh.is_legacy() && !p2sh.is_segwit() && !p2sh.is_taproot());
+        let p2wpkh = Address::dummy_p2wpkh(Bitcoin);
+        assert!(!p2wpkh.is_legacy() && p2wpkh.is_segwit() && !p2wpkh.is_taproot());
+        let p2tr = Address::dummy_p2tr(Bitcoin);
+        assert!(!p2tr.is_legacy() && p2tr.is_segwit() && p2tr.is_taproot());
+        let anchor = Address::from_witness_program(WitnessVersion::V1, vec![0x4e, 0x73], Bitcoin).unwrap();
+        assert!(anchor.is_segwit() && !anchor.is_taproot());
+    }
+
+    #[test]
+    fn test_testnet4() {
+        let segwit = Address::from_str_unchecked("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx").unwrap();
+        let testnet4 = segwit.require_network(Network::Testnet4).unwrap();
+        assert_eq!(testnet4.network, Testnet);
+        assert!(testnet4.is_valid_for_network(Network::Testnet4));
+
+        let legacy = Address::dummy_p2pkh(Network::Testnet4);
+        assert_eq!(legacy.to_string(), Address::dummy_p2pkh(Testnet).to_string());
+        assert!(legacy.is_valid_for_network(Testnet));
+        assert!(Address::dummy_p2tr(Network::Testnet4).to_string().starts_with("tb1p"));
+        assert!(!legacy.is_valid_for_network(Bitcoin));
+    }
 
+    #[test]
+    fn test_taproot_spk_array() {
+        let addr = Address::from_str("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0").unwrap();
+        let spk = addr.taproot_spk_array().unwrap();
+        assert_eq!(spk[..2], [0x51, 0x20]);
+        assert_eq!(spk[..], addr.script_pubkey()[..]);
+
+        let p2wpkh = Address::from_str("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4").unwrap();
+        assert!(p2wpkh.taproot_spk_array().is_none());
+        let anchor = Address::from_witness_program(WitnessVersion::V1, vec![0x4e, 0x73], Bitcoin).unwrap();
+        assert!(anchor.taproot_spk_array().is_none());
+    }
+
+    #[test]
+    fn test_diagnose() {
+        use bech32::ToBase32;
+
+        assert_eq!(Address::diagnose("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"), vec![]);
+        assert_eq!(Address::diagnose("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM"), vec![]);
+
+        // a 21-byte v0 program encoded with bech32m
+        let mut data = vec![bech32::u5::try_from_u8(0).unwrap()];
+        data.extend(vec![0x75u8; 21].to_base32());
+        let s = bech32::encode("bc", data, bech32::Variant::Bech32m).unwrap();
         assert_eq!(
-            addr.script_pubkey(),
-            hex_script!("a914162c5ea71c0b23f5b9022ef047c4a86470a5b07087")
+            Address::diagnose(&s),
+            vec![
+                Error::InvalidSegwitV0ProgramLength(21),
+                Error::InvalidBech32Variant {
+                    expected: bech32::Variant::Bech32,
+                    found: bech32::Variant::Bech32m,
+                },
+            ]
+        );
+        // only the first of them is reported when parsing
+        assert_eq!(Address::from_str(&s), Err(Error::InvalidSegwitV0ProgramLength(21)));
+
+        // same program with a broken checksum
+        let mut broken = s.clone();
+        let last = if broken.pop() == Some('q') { 'p' } else { 'q' };
+        broken.push(last);
+        assert_eq!(
+            Address::diagnose(&broken),
+            vec![
+                Error::Bech32(bech32::Error::InvalidChecksum),
+                Error::InvalidSegwitV0ProgramLength(21),
+            ]
+        );
+
+        assert_eq!(Address::diagnose("bc1gmk9yu"), vec![Error::EmptyBech32Payload]);
+        assert_eq!(Address::diagnose("bc1\u{e9}qqqqqqq").len(), 1);
+        assert_eq!(
+            Address::diagnose("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiN"),
+            vec![Address::from_str("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiN").unwrap_err()]
         );
-        assert_eq!(&addr.to_string(), "33iFwdLuRpW1uK1RTRqsoi8rR4NpDzk66k");
-        assert_eq!(addr.address_type(), Some(AddressType::P2sh));
-        roundtrips(&addr);
     }
 
     #[test]
-    fn test_p2sh_parse() {
-        let script = hex_script!("552103a765fc35b3f210b95223846b36ef62a4e53e34e2925270c2c7906b92c9f718eb2103c327511374246759ec8d0b89fa6c6b23b33e11f92c5bc155409d86de0c79180121038cae7406af1f12f4786d820a1466eec7bc5785a1b5e4a387eca6d797753ef6db2103252bfb9dcaab0cd00353f2ac328954d791270203d66c2be8b430f115f451b8a12103e79412d42372c55dd336f2eb6eb639ef9d74a22041ba79382c74da2338fe58ad21035049459a4ebc00e876a9eef02e72a3e70202d3d1f591fc0dd542f93f642021f82102016f682920d9723c61b27f562eb530c926c00106004798b6471e8c52c60ee02057ae");
-        let addr = Address::p2sh(&script, Testnet).unwrap();
-        assert_eq!(&addr.to_string(), "2N3zXjbwdTcPsJiy8sUK9FhWJhqQCxA8Jjr");
-        assert_eq!(addr.address_type(), Some(AddressType::P2sh));
+    fn test_default() {
+        let addr = Address::default();
+        assert_eq!(addr.address_type(), Some(AddressType::P2pkh));
+        assert_eq!(addr.network, Bitcoin);
+        assert_eq!(addr.to_string(), "1111111111111111111114oLvT2");
         roundtrips(&addr);
     }
 
     #[test]
-    fn test_p2sh_parse_for_large_script(){
-        let script = hex_script!("552103a765fc35b3f210b95223846b36ef62a4e53e34e2925270c2c7906b92c9f718eb2103c327511374246759ec8d0b89fa6c6b23b33e11f92c5bc155409d86de0c79180121038cae7406af1f12f4786d820a1466eec7bc5785a1b5e4a387eca6d797753ef6db2103252bfb9dcaab0cd00353f2ac328954d791270203d66c2be8b430f115f451b8a12103e79412d42372c55dd336f2eb6eb639ef9d74a22041ba79382c74da2338fe58ad21035049459a4ebc00e876a9eef02e72a3e70202d3d1f591fc0dd542f93f642021f82102016f682920d9723c61b27f562eb530c926c00106004798b6471e8c52c60ee02057ae12123122313123123ac1231231231231313123131231231231313212313213123123552103a765fc35b3f210b95223846b36ef62a4e53e34e2925270c2c7906b92c9f718eb2103c327511374246759ec8d0b89fa6c6b23b33e11f92c5bc155409d86de0c79180121038cae7406af1f12f4786d820a1466eec7bc5785a1b5e4a387eca6d797753ef6db2103252bfb9dcaab0cd00353f2ac328954d791270203d66c2be8b430f115f451b8a12103e79412d42372c55dd336f2eb6eb639ef9d74a22041ba79382c74da2338fe58ad21035049459a4ebc00e876a9eef02e72a3e70202d3d1f591fc0dd542f93f642021f82102016f682920d9723c61b27f562eb530c926c00106004798b6471e8c52c60ee02057ae12123122313123123ac1231231231231313123131231231231313212313213123123552103a765fc35b3f210b95223846b36ef62a4e53e34e2925270c2c7906b92c9f718eb2103c327511374246759ec8d0b89fa6c6b23b33e11f92c5bc155409d86de0c79180121038cae7406af1f12f4786d820a1466eec7bc5785a1b5e4a387eca6d797753ef6db2103252bfb9dcaab0cd00353f2ac328954d791270203d66c2be8b430f115f451b8a12103e79412d42372c55dd336f2eb6eb639ef9d74a22041ba79382c74da2338fe58ad21035049459a4ebc00e876a9eef02e72a3e70202d3d1f591fc0dd542f93f642021f82102016f682920d9723c61b27f562eb530c926c00106004798b6471e8c52c60ee02057ae12123122313123123ac1231231231231313123131231231231313212313213123123");
-        assert_eq!(Address::p2sh(&script, Testnet), Err(Error::ExcessiveScriptSize));
+    fn test_has_consistent_bech32_variant() {
+        for &(s, _) in BIP350_VALID {
+            assert!(Address::from_str(s).unwrap().has_consistent_bech32_variant());
+        }
+        assert!(Address::dummy_p2pkh(Bitcoin).has_consistent_bech32_variant());
+        for &version in WitnessVersion::all().iter() {
+            let addr = Address {
+                network: Testnet,
+                payload: Payload::WitnessProgram { version, program: vec![0; 21] },
+            };
+            assert!(addr.has_consistent_bech32_variant());
+        }
+
+        // BIP-350 vectors with the checksum variant of the other version
+        let v1_bech32 = "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqh2y7hd";
+        assert!(!has_bech32_variant_of(v1_bech32, WitnessVersion::V1));
+        assert!(has_bech32_variant_of(v1_bech32, WitnessVersion::V0));
+        let v0_bech32m = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kemeawh";
+        assert!(!has_bech32_variant_of(v0_bech32m, WitnessVersion::V0));
+        assert!(has_bech32_variant_of(v0_bech32m, WitnessVersion::V1));
     }
 
     #[test]
-    fn test_p2wpkh() {
-        // stolen from Bitcoin transaction: b3c8c2b6cfc335abbcb2c7823a8453f55d64b2b5125a9a61e8737230cdb8ce20
-        let mut key = hex_key!("033bc8c83c52df5712229a2f72206d90192366c36428cb0c12b6af98324d97bfbc");
-        let addr = Address::p2wpkh(&key, Bitcoin).unwrap();
-        assert_eq!(&addr.to_string(), "bc1qvzvkjn4q3nszqxrv3nraga2r822xjty3ykvkuw");
-        assert_eq!(addr.address_type(), Some(AddressType::P2wpkh));
-        roundtrips(&addr);
-
-        // Test uncompressed pubkey
-        key.compressed = false;
-        assert_eq!(Address::p2wpkh(&key, Bitcoin), Err(Error::UncompressedPubkey));
+    fn test_ticker() {
+        assert_eq!(ticker(Bitcoin), "BTC");
+        assert_eq!(ticker(Testnet), "tBTC");
+        assert_eq!(ticker(Network::Testnet4), "tBTC");
+        assert_eq!(ticker(Network::Signet), "tBTC");
+        assert_eq!(ticker(Network::Regtest), "tBTC");
     }
 
     #[test]
-    fn test_p2wsh() {
-        // stolen from Bitcoin transaction 5df912fda4becb1c29e928bec8d64d93e9ba8efa9b5b405bd683c86fd2c65667
-        let script = hex_script!("52210375e00eb72e29da82b89367947f29ef34afb75e8654f6ea368e0acdfd92976b7c2103a1b26313f430c4b15bb1fdce663207659d8cac749a0e53d70eff01874496feff2103c96d495bfdd5ba4145e3e046fee45e84a8a48ad05bd8dbb395c011a32cf9f88053ae");
-        let addr = Address::p2wsh(&script, Bitcoin);
+    fn test_unsupported_address_scheme() {
         assert_eq!(
-            &addr.to_string(),
-            "bc1qwqdg6squsna38e46795at95yu9atm8azzmyvckulcc7kytlcckxswvvzej"
+            Address::from_str("bitcoincash:qpm2qsznhks23z7629mms6s4cwef74vcwvy22gdx6a"),
+            Err(Error::UnsupportedAddressScheme("bitcoincash".to_owned()))
         );
-        assert_eq!(addr.address_type(), Some(AddressType::P2wsh));
-        roundtrips(&addr);
+        assert_eq!(
+            Address::from_str("bitcoin:132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM"),
+            Err(Error::UnsupportedAddressScheme("bitcoin".to_owned()))
+        );
+        // not a scheme
+        match Address::from_str(":132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM") {
+            Err(Error::UnsupportedAddressScheme(_)) | Ok(_) => panic!("expected a decoding error"),
+            Err(_) => {}
+        }
     }
 
     #[test]
-    fn test_p2shwpkh() {
-        // stolen from Bitcoin transaction: ad3fd9c6b52e752ba21425435ff3dd361d6ac271531fc1d2144843a9f550ad01
-        let mut key = hex_key!("026c468be64d22761c30cd2f12cbc7de255d592d7904b1bab07236897cc4c2e766");
-        let addr = Address::p2shwpkh(&key, Bitcoin).unwrap();
-        assert_eq!(&addr.to_string(), "3QBRmWNqqBGme9er7fMkGqtZtp4gjMFxhE");
-        assert_eq!(addr.address_type(), Some(AddressType::P2sh));
-        roundtrips(&addr);
+    fn test_p2tr_script_only() {
+        let secp = Secp256k1::verification_only();
+        let nums = schnorrsig::PublicKey::from_slice(&NUMS_INTERNAL_KEY).unwrap();
+        // H = lift_x(sha256(uncompressed generator))
+        let generator = hex!("0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8");
+        assert_eq!(::hashes::sha256::Hash::hash(&generator).into_inner(), NUMS_INTERNAL_KEY);
 
-        // Test uncompressed pubkey
-        key.compressed = false;
-        assert_eq!(Address::p2wpkh(&key, Bitcoin), Err(Error::UncompressedPubkey));
-    }
+        let leaf = hex_script!("20d85a959b0290bf19bb89ed43c916be835475d013da4b362117393e25a48229b8ac");
+        let mut leaf_engine = TapLeafHash::engine();
+        leaf_engine.input(&[0xc0]);
+        leaf.consensus_encode(&mut leaf_engine).unwrap();
+        let merkle_root = TapBranchHash::from_inner(TapLeafHash::from_engine(leaf_engine).into_inner());
 
-    #[test]
-    fn test_p2shwsh() {
-        // stolen from Bitcoin transaction f9ee2be4df05041d0e0a35d7caa3157495ca4f93b233234c9967b6901dacf7a9
-        let script = hex_script!("522103e5529d8eaa3d559903adb2e881eb06c86ac2574ffa503c45f4e942e2a693b33e2102e5f10fcdcdbab211e0af6a481f5532536ec61a5fdbf7183770cf8680fe729d8152ae");
-        let addr = Address::p2shwsh(&script, Bitcoin);
-        assert_eq!(&addr.to_string(), "36EqgNnsWW94SreZgBWc1ANC6wpFZwirHr");
-        assert_eq!(addr.address_type(), Some(AddressType::P2sh));
-        roundtrips(&addr);
+        let addr = Address::p2tr_script_only(&secp, merkle_root, Bitcoin);
+        assert_eq!(addr, Address::p2tr_single_leaf(&secp, nums, &leaf, Bitcoin));
+        assert_ne!(addr, Address::p2tr(nums, Bitcoin));
+        assert!(addr.is_taproot());
     }
 
     #[test]
@@ -940,83 +3998,83 @@ mod tests {
 
     #[test]
     fn test_bip173_350_vectors() {
-        // Test vectors valid under both BIP-173 and BIP-350
-        let valid_vectors = [
-            ("BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4", "0014751e76e8199196d454941c45d1b3a323f1433bd6"),
-            ("tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7", "00201863143c14c5166804bd19203356da136c985678cd4d27a1b8c6329604903262"),
-            ("bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kt5nd6y", "5128751e76e8199196d454941c45d1b3a323f1433bd6751e76e8199196d454941c45d1b3a323f1433bd6"),
-            ("BC1SW50QGDZ25J", "6002751e"),
-            ("bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs", "5210751e76e8199196d454941c45d1b3a323"),
-            ("tb1qqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesrxh6hy", "0020000000c4a5cad46221b2a187905e5266362b99d5e91c6ce24d165dab93e86433"),
-            ("tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c", "5120000000c4a5cad46221b2a187905e5266362b99d5e91c6ce24d165dab93e86433"),
-            ("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0", "512079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
-        ];
-        for vector in &valid_vectors {
-            let addr: Address = vector.0.parse().unwrap();
-            assert_eq!(&addr.script_pubkey().as_bytes().to_hex(), vector.1);
+        for &(s, spk) in BIP350_VALID {
+            let addr: Address = s.parse().unwrap();
+            assert_eq!(addr.script_pubkey().as_bytes().to_hex(), spk);
             roundtrips(&addr);
+            assert_eq!(Address::validate(s), Ok(()));
         }
 
-        let invalid_vectors = [
-            // 1. BIP-350 test vectors
-            // Invalid human-readable part
-            "tc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq5zuyut",
-            // Invalid checksums (Bech32 instead of Bech32m):
-            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqh2y7hd",
-            "tb1z0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqglt7rf",
-            "BC1S0XLXVLHEMJA6C4DQV22UAPCTQUPFHLXM9H8Z3K2E72Q4K9HCZ7VQ54WELL",
-            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kemeawh",
-            "tb1q0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq24jc47",
-            // Invalid character in checksum
-            "bc1p38j9r5y49hruaue7wxjce0updqjuyyx0kh56v8s25huc6995vvpql3jow4",
-            // Invalid witness version
-            "BC130XLXVLHEMJA6C4DQV22UAPCTQUPFHLXM9H8Z3K2E72Q4K9HCZ7VQ7ZWS8R",
-            // Invalid program length (1 byte)
-            "bc1pw5dgrnzv",
-            // Invalid program length (41 bytes)
-            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7v8n0nx0muaewav253zgeav",
-            // Invalid program length for witness version 0 (per BIP141)
-            "BC1QR508D6QEJXTDG4Y5R3ZARVARYV98GJ9P",
-            // Mixed case
-            "tb1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq47Zagq",
-            // zero padding of more than 4 bits
-            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7v07qwwzcrf",
-            // Non-zero padding in 8-to-5 conversion
-            "tb1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vpggkg4j",
-            // Empty data section
-            "bc1gmk9yu",
-
-            // 2. BIP-173 test vectors
-            // Invalid human-readable part
-            "tc1qw508d6qejxtdg4y5r3zarvary0c5xw7kg3g4ty",
-            // Invalid checksum
-            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5",
-            // Invalid witness version
-            "BC13W508D6QEJXTDG4Y5R3ZARVARY0C5XW7KN40WF2",
-            // Invalid program length
-            "bc1rw5uspcuh",
-            // Invalid program length
-            "bc10w508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kw5rljs90",
-            // Invalid program length for witness version 0 (per BIP141)
-            "BC1QR508D6QEJXTDG4Y5R3ZARVARYV98GJ9P",
-            // Mixed case
-            "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sL5k7",
-            // zero padding of more than 4 bits
-            "bc1zw508d6qejxtdg4y5r3zarvaryvqyzf3du",
-            // Non-zero padding in 8-to-5 conversion
-            "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3pjxtptv",
-            // Final test for empty data section is the same as above in BIP-350
-
-            // 3. BIP-173 valid test vectors obsolete by BIP-350
-            "bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7k7grplx",
-            "BC1SW50QA3JX3S",
-            "bc1zw508d6qejxtdg4y5r3zarvaryvg6kdaj",
-        ];
-        for vector in &invalid_vectors {
+        for vector in BIP350_INVALID {
             assert!(vector.parse::<Address>().is_err());
+            assert_eq!(Address::validate(vector), Address::from_str(vector).map(|_| ()));
+        }
+
+        // errors outside of the BIP vectors
+        let long = "1".repeat(MAX_ADDRESS_LEN + 1);
+        let others = [
+            "",
+            &long,
+            "bitcoin:1QJVDzdqb1VpbDK7uSbBwNAfG8EsRqTFBC",
+            "1QJVDZDQB1VPBDK7USBBWNAFG8ESRQTFBC",
+            "1QJVDzdqb1VpbDK7uSbBwNAfG8EsRqTFBD",
+            "3P14159f73E4gFr7JterCCQh9QjiTjiZrG",
+            "2MzQwSSnBHWHqSAqtTVQ6v47XtaisrJa1Vc",
+            "7Zu4SAPgWLf9WFbSDWKV1y6nHkEVQnUFmKqF",
+            "bc1z0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqdgu9vx",
+            "bc1qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq0fs8gwd",
+        ];
+        for vector in others.iter() {
+            assert_eq!(Address::validate(vector), Address::from_str(vector).map(|_| ()), "{}", vector);
         }
     }
 
+    #[test]
+    fn test_unchecked_address() {
+        let testnet = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+        let unchecked = Address::from_str_unchecked(testnet).unwrap();
+        assert!(!unchecked.is_valid_for_network(Bitcoin));
+        assert!(unchecked.is_valid_for_network(Network::Signet));
+        assert_eq!(
+            unchecked.clone().require_network(Bitcoin),
+            Err(Error::WrongNetwork { required: Bitcoin, found: Testnet })
+        );
+        assert_eq!(unchecked.clone().require_network(Testnet), Ok(Address::from_str(testnet).unwrap()));
+        assert_eq!(unchecked.clone().require_network(Network::Signet), Ok(Address::from_str(testnet).unwrap()));
+        assert_eq!(unchecked.assume_checked(), Address::from_str(testnet).unwrap());
+
+        let mainnet = Address::from_str_unchecked("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM").unwrap();
+        assert_eq!(mainnet.assume_checked().network, Bitcoin);
+
+        assert_eq!(Address::from_str_unchecked("bc1pw5dgrnzv"), Err(Error::WitnessProgramTooShort(1)));
+    }
+
+    #[test]
+    fn test_address_too_long() {
+        let s = format!("bc1q{}", "q".repeat(100_000));
+        assert_eq!(Address::from_str(&s), Err(Error::AddressTooLong(100_004)));
+        assert_eq!(
+            AddressEncoding::decode(&s, PUBKEY_ADDRESS_PREFIX_MAIN, SCRIPT_ADDRESS_PREFIX_MAIN, "bc"),
+            Err(Error::AddressTooLong(100_004))
+        );
+
+        // just within the limit, the decoders report their own errors
+        let s = "1".repeat(MAX_ADDRESS_LEN);
+        assert_ne!(Address::from_str(&s), Err(Error::AddressTooLong(MAX_ADDRESS_LEN)));
+    }
+
+    #[test]
+    fn test_witness_program_length_errors() {
+        assert_eq!(
+            Address::from_str("bc1pw5dgrnzv"),
+            Err(Error::WitnessProgramTooShort(1))
+        );
+        assert_eq!(
+            Address::from_str("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7v8n0nx0muaewav253zgeav"),
+            Err(Error::WitnessProgramTooLong(41))
+        );
+    }
+
     #[test]
     #[cfg(feature = "serde")]
     fn test_json_serialize() {
@@ -1079,6 +4137,88 @@ mod tests {
         );
     }
 
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde_as_network() {
+        use serde_json;
+        use self::as_network::{MainnetAddress, TestnetAddress};
+
+        let mainnet = "\"bc1qvzvkjn4q3nszqxrv3nraga2r822xjty3ykvkuw\"";
+        let testnet = "\"tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7\"";
+
+        let addr: MainnetAddress = serde_json::from_str(mainnet).unwrap();
+        assert_eq!(serde_json::to_string(&addr).unwrap(), mainnet);
+        assert!(serde_json::from_str::<MainnetAddress>(testnet).is_err());
+
+        let addr: TestnetAddress = serde_json::from_str(testnet).unwrap();
+        assert_eq!(serde_json::to_string(&addr).unwrap(), testnet);
+        assert!(serde_json::from_str::<TestnetAddress>(mainnet).is_err());
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Withdrawal {
+            #[serde(with = "::util::address::as_network::mainnet")]
+            destination: Address,
+        }
+        let json = format!("{{\"destination\":{}}}", mainnet);
+        let withdrawal: Withdrawal = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&withdrawal).unwrap(), json);
+        let json = format!("{{\"destination\":{}}}", testnet);
+        assert!(serde_json::from_str::<Withdrawal>(&json).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_full_serde() {
+        use serde_json;
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Stored {
+            #[serde(with = "::util::address::full_serde")]
+            address: Address,
+        }
+
+        let signet = Address::dummy_p2wpkh(Network::Signet);
+        let stored = Stored { address: signet.clone() };
+        let json = serde_json::to_string(&stored).unwrap();
+        assert_eq!(
+            json,
+            format!("{{\"address\":{{\"address\":\"{}\",\"network\":\"signet\"}}}}", signet),
+        );
+        let restored: Stored = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.address.network, Network::Signet);
+        assert_eq!(restored, stored);
+
+        // the plain serde implementation loses the network
+        let plain: Address = serde_json::from_str(&serde_json::to_string(&signet).unwrap()).unwrap();
+        assert_eq!(plain.network, Testnet);
+
+        let json = format!("{{\"address\":{{\"address\":\"{}\",\"network\":\"bitcoin\"}}}}", signet);
+        assert!(serde_json::from_str::<Stored>(&json).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_from_rpc_field() {
+        use serde_json;
+
+        let p2pkh = Address::from_str("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM").unwrap();
+        let p2wpkh = Address::from_str("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4").unwrap();
+
+        let mut single = serde_json::Deserializer::from_str("\"132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM\"");
+        assert_eq!(Address::from_rpc_field(&mut single, Bitcoin).unwrap(), vec![p2pkh.clone()]);
+
+        let json = "[\"132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM\", \"bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4\"]";
+        let mut array = serde_json::Deserializer::from_str(json);
+        assert_eq!(Address::from_rpc_field(&mut array, Bitcoin).unwrap(), vec![p2pkh, p2wpkh]);
+
+        let mut array = serde_json::Deserializer::from_str(json);
+        assert!(Address::from_rpc_field(&mut array, Testnet).is_err());
+        let mut non_string = serde_json::Deserializer::from_str("[\"132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM\", 1]");
+        assert!(Address::from_rpc_field(&mut non_string, Bitcoin).is_err());
+        let mut object = serde_json::Deserializer::from_str("{}");
+        assert!(Address::from_rpc_field(&mut object, Bitcoin).is_err());
+    }
+
     #[test]
     fn test_qr_string() {
         for el in  ["132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM", "33iFwdLuRpW1uK1RTRqsoi8rR4NpDzk66k"].iter() {
@@ -1092,29 +4232,55 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_txout_weight() {
+        use blockdata::transaction::TxOut;
+        use consensus::encode::serialize;
+
+        let p2wpkh = Address::from_str("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4").unwrap();
+        assert_eq!(p2wpkh.txout_weight(), 124);
+
+        for addr in &[
+            "132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM",
+            "33iFwdLuRpW1uK1RTRqsoi8rR4NpDzk66k",
+            "bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3",
+            "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr",
+        ] {
+            let addr = Address::from_str(addr).unwrap();
+            let txout = TxOut { value: 0, script_pubkey: addr.script_pubkey() };
+            assert_eq!(addr.txout_weight(), serialize(&txout).len() * WITNESS_SCALE_FACTOR);
+        }
+    }
+
+    #[test]
+    fn test_qr_uses_alphanumeric_mode() {
+        // QR alphanumeric mode charset: 0-9, A-Z, space and $%*+-./:
+        fn is_qr_alphanumeric(s: &str) -> bool {
+            s.chars().all(|c| c.is_ascii_digit() || c.is_ascii_uppercase() || " $%*+-./:".contains(c))
+        }
+
+        let addr = Address::from_str("bc1qvzvkjn4q3nszqxrv3nraga2r822xjty3ykvkuw").unwrap();
+        assert!(addr.qr_uses_alphanumeric_mode());
+        assert!(is_qr_alphanumeric(&addr.to_qr_uri()));
+
+        let addr = Address::from_str("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM").unwrap();
+        assert!(!addr.qr_uses_alphanumeric_mode());
+        assert!(!is_qr_alphanumeric(&addr.to_qr_uri()));
+    }
+
     #[test]
     fn test_valid_networks() {
         let legacy_payload = &[
             Payload::PubkeyHash(PubkeyHash::default()),
             Payload::ScriptHash(ScriptHash::default())
         ];
-        let segwit_payload = (0..=16).map(|version| {
+        let segwit_payload = WitnessVersion::all().iter().map(|&version| {
             Payload::WitnessProgram {
-                version: WitnessVersion::from_num(version).unwrap(),
+                version,
                 program: vec![]
             }
         }).collect::<Vec<_>>();
 
-        const LEGACY_EQUIVALENCE_CLASSES: &[&[Network]] = &[
-            &[Network::Bitcoin],
-            &[Network::Testnet, Network::Regtest, Network::Signet],
-        ];
-        const SEGWIT_EQUIVALENCE_CLASSES: &[&[Network]] = &[
-            &[Network::Bitcoin],
-            &[Network::Regtest],
-            &[Network::Testnet, Network::Signet],
-        ];
-
         fn test_addr_type(payloads: &[Payload], equivalence_classes: &[&[Network]]) {
             for pl in payloads {
                 for addr_net in equivalence_classes.iter().map(|ec| ec.iter()).flatten() {
@@ -1148,4 +4314,150 @@ mod tests {
         test_addr_type(legacy_payload, LEGACY_EQUIVALENCE_CLASSES);
         test_addr_type(&segwit_payload, SEGWIT_EQUIVALENCE_CLASSES);
     }
+
+    #[test]
+    fn test_base58_wrong_hash_length() {
+        // A valid P2PKH prefix followed by a 19-byte hash must error, not panic
+        let mut data = vec![PUBKEY_ADDRESS_PREFIX_MAIN];
+        data.extend_from_slice(&[0xab; 19]);
+        let s = base58::check_encode_slice(&data);
+        assert_eq!(Address::from_str(&s), Err(Error::Base58(base58::Error::InvalidLength(20))));
+
+        let err = PubkeyHash::from_slice(&[0xab; 19]).unwrap_err();
+        assert_eq!(Error::from(err), Error::InvalidHashLength(19));
+    }
+
+    #[test]
+    fn test_base58_uppercase_suspected() {
+        let addr = "132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM";
+        assert!(Address::from_str(addr).is_ok());
+        assert_eq!(
+            Address::from_str(&addr.to_uppercase()),
+            Err(Error::Base58UppercaseSuspected)
+        );
+        // Other corruptions still report the underlying base58 error
+        assert_eq!(
+            Address::from_str("132F25rTsvBdp9JzLLBHP5mvGY66i1xdi0"),
+            Err(Error::Base58(base58::Error::BadByte(b'0')))
+        );
+    }
+
+    #[test]
+    fn test_base58_extra_leading_zero() {
+        // An extra leading '1' is an extra leading zero byte, which changes the decoded length
+        let addr = Address::from_str("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM").unwrap();
+        let mut data = vec![0, PUBKEY_ADDRESS_PREFIX_MAIN];
+        match addr.payload {
+            Payload::PubkeyHash(ref hash) => data.extend_from_slice(&hash[..]),
+            _ => unreachable!(),
+        }
+        let s = base58::check_encode_slice(&data);
+        assert!(s.starts_with("11"));
+        assert_eq!(Address::from_str(&s), Err(Error::Base58(base58::Error::InvalidLength(22))));
+
+        // Just prepending the '1' also invalidates the checksum
+        match Address::from_str(&format!("1{}", addr)) {
+            Err(Error::Base58(base58::Error::BadChecksum(..))) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_encode_to() {
+        let first = Address::from_str("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM").unwrap();
+        let second = Address::from_str("bc1qvzvkjn4q3nszqxrv3nraga2r822xjty3ykvkuw").unwrap();
+
+        let mut buf = String::new();
+        first.encode_to(&mut buf).unwrap();
+        second.encode_to(&mut buf).unwrap();
+        assert_eq!(buf, format!("{}{}", first, second));
+    }
+
+    #[test]
+    fn test_encode_into() {
+        let mut buf = String::new();
+        for i in 0..1000u32 {
+            let mut hash = [0u8; 20];
+            hash[0] = i as u8;
+            hash[1] = (i >> 8) as u8;
+            let payload = if i % 2 == 0 {
+                Payload::PubkeyHash(PubkeyHash::from_inner(hash))
+            } else {
+                Payload::WitnessProgram { version: WitnessVersion::V0, program: hash.to_vec() }
+            };
+            let addr = Address { network: Bitcoin, payload };
+            addr.encode_into(&mut buf);
+            assert_eq!(buf, addr.to_string());
+        }
+    }
+
+    #[test]
+    fn test_bech32_variant() {
+        let addr = Address::from_str("bc1qvzvkjn4q3nszqxrv3nraga2r822xjty3ykvkuw").unwrap();
+        assert_eq!(addr.bech32_variant(), Some(bech32::Variant::Bech32));
+
+        let addr = Address::from_str("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0").unwrap();
+        assert_eq!(addr.bech32_variant(), Some(bech32::Variant::Bech32m));
+
+        let addr = Address::from_str("132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM").unwrap();
+        assert_eq!(addr.bech32_variant(), None);
+    }
+
+    #[test]
+    fn test_witness_version_all() {
+        let all = WitnessVersion::all();
+        assert_eq!(all.len(), 17);
+        for (i, version) in all.iter().enumerate() {
+            assert_eq!(version.into_num() as usize, i);
+            assert_eq!(WitnessVersion::from_num(version.into_num()), Ok(*version));
+        }
+        assert!(all.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_witness_version_next_prev() {
+        assert_eq!(WitnessVersion::V0.next(), Some(WitnessVersion::V1));
+        assert_eq!(WitnessVersion::V0.prev(), None);
+        assert_eq!(WitnessVersion::V7.next(), Some(WitnessVersion::V8));
+        assert_eq!(WitnessVersion::V7.prev(), Some(WitnessVersion::V6));
+        assert_eq!(WitnessVersion::V16.next(), None);
+        assert_eq!(WitnessVersion::V16.prev(), Some(WitnessVersion::V15));
+
+        assert_eq!(WitnessVersion::V7.saturating_next(), WitnessVersion::V8);
+        assert_eq!(WitnessVersion::V16.saturating_next(), WitnessVersion::V16);
+
+        let all = WitnessVersion::all();
+        for pair in all.windows(2) {
+            assert_eq!(pair[0].next(), Some(pair[1]));
+            assert_eq!(pair[1].prev(), Some(pair[0]));
+        }
+    }
+
+    #[test]
+    fn test_witness_version_opcode_roundtrip() {
+        for version in WitnessVersion::all().iter() {
+            let opcode = version.to_opcode();
+            assert_eq!(opcode, opcodes::All::from(*version));
+            assert_eq!(WitnessVersion::from_opcode(opcode), Ok(*version));
+        }
+        assert_eq!(WitnessVersion::V0.to_opcode(), opcodes::all::OP_PUSHBYTES_0);
+        assert_eq!(WitnessVersion::V16.to_opcode(), opcodes::all::OP_PUSHNUM_16);
+    }
+
+    #[test]
+    fn test_script_pubkey_len() {
+        let addresses = [
+            "132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM",
+            "33iFwdLuRpW1uK1RTRqsoi8rR4NpDzk66k",
+            "bc1qvzvkjn4q3nszqxrv3nraga2r822xjty3ykvkuw",
+            "bc1qwqdg6squsna38e46795at95yu9atm8azzmyvckulcc7kytlcckxswvvzej",
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+        ];
+        for el in addresses.iter() {
+            let addr = Address::from_str(el).unwrap();
+            assert_eq!(addr.script_pubkey_len(), addr.script_pubkey().len(), "{}", el);
+        }
+        let taproot = Address::from_str(addresses[4]).unwrap();
+        assert_eq!(taproot.script_pubkey_len(), 34);
+    }
 }